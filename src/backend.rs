@@ -1,25 +1,180 @@
-use tower_lsp::jsonrpc::Error;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use tokio::sync::RwLock;
+use tower_lsp::jsonrpc::{Error, ErrorCode};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
+use crate::cli::collect_files;
+use crate::config::load_config_for;
+use crate::consts::RUN_FILE_COMMAND;
+use crate::lint::LintConfig;
+use crate::methods::call_hierarchy::{
+    incoming_calls_method, outgoing_calls_method, prepare_call_hierarchy_method,
+};
+use crate::methods::code_action::code_action_method;
+use crate::methods::code_lens::code_lens_method;
 use crate::methods::completion::completion_method;
+use crate::methods::diagnostics::{get_diagnostics, get_lint_settings};
+use crate::methods::document_highlight::document_highlight_method;
+use crate::methods::errors::{NO_FILE_OR_DIRECTORY, UNKNOWN_COMMAND};
+use crate::methods::folding_range::folding_range_method;
+use crate::methods::formatting::{
+    formatting_method, on_type_formatting_method, range_formatting_method,
+};
 use crate::methods::hover::method::hover_method;
 use crate::methods::initialize::initialize;
+use crate::methods::inlay_hint::inlay_hint_method;
+use crate::methods::run_file::{run_file_method, run_file_uri};
+use crate::methods::selection_range::selection_range_method;
 
 pub struct Backend {
     pub client: Client,
+    pub documents: RwLock<HashMap<Url, String>>,
+    /// Lint rule levels sent by the editor in `workspace/didChangeConfiguration`.
+    pub lint_settings: RwLock<LintConfig>,
+    pub workspace_folders: RwLock<Vec<PathBuf>>,
+}
+
+impl Backend {
+    pub fn new(client: Client) -> Self {
+        Backend {
+            client,
+            documents: RwLock::new(HashMap::new()),
+            lint_settings: RwLock::new(LintConfig::new()),
+            workspace_folders: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Returns the open document's text, or the file on disk when the editor
+    /// has not sent it.
+    async fn document_source(&self, uri: &Url) -> Result<String, Error> {
+        if let Some(source) = self.documents.read().await.get(uri) {
+            return Ok(source.clone());
+        }
+        let no_file = || Error {
+            code: ErrorCode::InvalidParams,
+            message: String::from_utf8_lossy(NO_FILE_OR_DIRECTORY.as_bytes()),
+            data: None,
+        };
+        let path = uri.to_file_path().map_err(|()| no_file())?;
+        fs::read_to_string(path).map_err(|_| no_file())
+    }
+
+    /// The `.severo` files of the workspace folders and the open documents,
+    /// with the editor's text for the latter.
+    async fn workspace_sources(&self) -> Vec<(Url, String)> {
+        let mut paths = Vec::new();
+        for folder in self.workspace_folders.read().await.iter() {
+            let _ = collect_files(folder, &mut paths);
+        }
+        let documents = self.documents.read().await;
+        let mut sources: Vec<(Url, String)> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let uri = Url::from_file_path(&path).ok()?;
+                match documents.get(&uri) {
+                    Some(source) => Some((uri, source.clone())),
+                    None => Some((uri, fs::read_to_string(&path).ok()?)),
+                }
+            })
+            .collect();
+        for (uri, source) in documents.iter() {
+            if sources.iter().all(|(other, _)| other != uri) {
+                sources.push((uri.clone(), source.clone()));
+            }
+        }
+        sources
+    }
+
+    /// Publishes the diagnostics of `source`, with the error that ended its
+    /// last run when there is one.
+    async fn publish_diagnostics(
+        &self,
+        uri: Url,
+        source: String,
+        version: Option<i32>,
+        runtime_error: Option<Diagnostic>,
+    ) {
+        let lint_config = load_config_for(&uri).lint_config(&*self.lint_settings.read().await);
+        let mut diagnostics = get_diagnostics(&uri, source, &lint_config);
+        diagnostics.extend(runtime_error);
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _params: InitializeParams) -> Result<InitializeResult, Error> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult, Error> {
+        #[allow(deprecated)]
+        let folders: Vec<Url> = match params.workspace_folders {
+            Some(folders) => folders.into_iter().map(|folder| folder.uri).collect(),
+            None => params.root_uri.into_iter().collect(),
+        };
+        *self.workspace_folders.write().await = folders
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect();
         initialize()
     }
 
     async fn initialized(&self, _params: InitializedParams) {}
 
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        *self.lint_settings.write().await = get_lint_settings(&params.settings);
+        let documents: Vec<(Url, String)> = self
+            .documents
+            .read()
+            .await
+            .iter()
+            .map(|(uri, source)| (uri.clone(), source.clone()))
+            .collect();
+        for (uri, source) in documents {
+            self.publish_diagnostics(uri, source, None, None).await;
+        }
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let document = params.text_document;
+        self.documents
+            .write()
+            .await
+            .insert(document.uri.clone(), document.text.clone());
+        self.publish_diagnostics(document.uri, document.text, Some(document.version), None)
+            .await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        if let Some(change) = params.content_changes.into_iter().last() {
+            self.documents
+                .write()
+                .await
+                .insert(params.text_document.uri.clone(), change.text.clone());
+            self.publish_diagnostics(
+                params.text_document.uri,
+                change.text,
+                Some(params.text_document.version),
+                None,
+            )
+            .await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents
+            .write()
+            .await
+            .remove(&params.text_document.uri);
+    }
+
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>, Error> {
-        hover_method(params)
+        let uri = &params.text_document_position_params.text_document.uri;
+        let source = self.document_source(uri).await?;
+        hover_method(params, source)
     }
 
     async fn completion(
@@ -29,6 +184,122 @@ impl LanguageServer for Backend {
         completion_method(params)
     }
 
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>, Error> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let source = self.document_source(uri).await?;
+        document_highlight_method(params, source)
+    }
+
+    async fn code_action(
+        &self,
+        params: CodeActionParams,
+    ) -> Result<Option<CodeActionResponse>, Error> {
+        let source = self.document_source(&params.text_document.uri).await?;
+        code_action_method(params, source)
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>, Error> {
+        let source = self.document_source(&params.text_document.uri).await?;
+        code_lens_method(params, source)
+    }
+
+    async fn folding_range(
+        &self,
+        params: FoldingRangeParams,
+    ) -> Result<Option<Vec<FoldingRange>>, Error> {
+        let source = self.document_source(&params.text_document.uri).await?;
+        folding_range_method(params, source)
+    }
+
+    async fn formatting(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>, Error> {
+        let source = self.document_source(&params.text_document.uri).await?;
+        formatting_method(params, source)
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>, Error> {
+        let source = self.document_source(&params.text_document.uri).await?;
+        range_formatting_method(params, source)
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>, Error> {
+        let uri = &params.text_document_position.text_document.uri;
+        let source = self.document_source(uri).await?;
+        on_type_formatting_method(params, source)
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>, Error> {
+        let source = self.document_source(&params.text_document.uri).await?;
+        selection_range_method(params, source)
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>, Error> {
+        let source = self.document_source(&params.text_document.uri).await?;
+        inlay_hint_method(params, source)
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>, Error> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let source = self.document_source(uri).await?;
+        let mut files = self.workspace_sources().await;
+        if files.iter().all(|(other, _)| other != uri) {
+            files.push((uri.clone(), source));
+        }
+        prepare_call_hierarchy_method(params, files)
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>, Error> {
+        incoming_calls_method(params, self.workspace_sources().await)
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>, Error> {
+        outgoing_calls_method(params, self.workspace_sources().await)
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> Result<Option<serde_json::Value>, Error> {
+        match params.command.as_str() {
+            RUN_FILE_COMMAND => {
+                let uri = run_file_uri(&params.arguments)?;
+                let source = self.document_source(&uri).await?;
+                let runtime_error = run_file_method(&self.client, source.clone()).await;
+                self.publish_diagnostics(uri, source, None, runtime_error)
+                    .await;
+                Ok(None)
+            }
+            _ => Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: String::from_utf8_lossy(UNKNOWN_COMMAND.as_bytes()),
+                data: None,
+            }),
+        }
+    }
+
     async fn shutdown(&self) -> Result<(), Error> {
         Ok(())
     }
@@ -43,11 +314,15 @@ mod tests {
     use tokio::io::AsyncWriteExt;
 
     use crate::{
-        consts::{SERVER_NAME, SERVER_VERSION},
-        methods::{errors::NO_FILE_OR_DIRECTORY, hover::texts::VAR},
+        consts::{RUN_FILE_COMMAND, SERVER_NAME, SERVER_VERSION},
+        methods::{
+            errors::{MISSING_FILE_ARGUMENT, NO_FILE_OR_DIRECTORY},
+            hover::texts::VAR,
+        },
         tests::helpers::{
-            assert_outputs, build_response, completion_request, create_lsp, format_request,
-            format_response, get_response_string, hover_request, init_lsp, initialize_request,
+            assert_outputs, build_notification, build_response, completion_request, create_lsp,
+            did_open_notification, execute_command_request, format_request, format_response,
+            formatting_request, get_response_string, hover_request, init_lsp, initialize_request,
             shutdown_request,
         },
     };
@@ -58,7 +333,25 @@ mod tests {
         let expected_response = format_response(build_response(
             request_id,
             Ok(json!({
-                "capabilities":{"hoverProvider":true,"completionProvider":{}},
+                "capabilities":{
+                    "textDocumentSync":1,
+                    "selectionRangeProvider":true,
+                    "hoverProvider":true,
+                    "completionProvider":{},
+                    "documentHighlightProvider":true,
+                    "codeActionProvider":true,
+                    "codeLensProvider":{"resolveProvider":false},
+                    "documentFormattingProvider":true,
+                    "documentRangeFormattingProvider":true,
+                    "documentOnTypeFormattingProvider":{
+                        "firstTriggerCharacter":"}",
+                        "moreTriggerCharacter":["\n"]
+                    },
+                    "foldingRangeProvider":true,
+                    "executeCommandProvider":{"commands":[RUN_FILE_COMMAND]},
+                    "callHierarchyProvider":true,
+                    "inlayHintProvider":true
+                },
                 "serverInfo":{"name":SERVER_NAME,"version":SERVER_VERSION}
             })),
         ));
@@ -105,6 +398,39 @@ mod tests {
         assert_outputs(expected_response, response)
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn hover_doc_comment() {
+        let (mut req_client, resp_client) = init_lsp().await;
+        let request_id = 3;
+        let expected_response = format_response(build_response(
+            request_id,
+            Ok(json!({
+                "contents":{
+                    "kind":"markdown",
+                    "value":"\n### greeting\n\nThe message shown on startup.\n```severo\ngreeting = \"Hello\"\n```"
+                },
+                "range":{
+                    "end":{"character":14,"line":3},
+                    "start":{"character":6,"line":3}
+                }
+            })),
+        ));
+
+        let current_dir = env::current_dir().expect("Failed to get current directory");
+        let hover_mock = current_dir
+            .join("src/tests/mocks/doc_comment.severo")
+            .to_string_lossy()
+            .to_string();
+        let hover_request = hover_request(request_id, hover_mock, 3, 8);
+        req_client
+            .write_all(format_request(hover_request).as_bytes())
+            .await
+            .unwrap();
+
+        let response = get_response_string(resp_client).await;
+        assert_outputs(expected_response, response)
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn hover_error_with_path() {
         let (mut req_client, resp_client) = init_lsp().await;
@@ -277,6 +603,107 @@ mod tests {
         assert_outputs(expected_response, response)
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn publish_diagnostics_on_open() {
+        let (mut req_client, resp_client) = init_lsp().await;
+        let uri = "file:///tmp/diagnostics.severo".to_string();
+        let expected_response = format_response(build_notification(
+            "textDocument/publishDiagnostics",
+            json!({
+                "uri":uri,
+                "version":1,
+                "diagnostics":[{
+                    "range":{
+                        "start":{"line":0,"character":7},
+                        "end":{"line":0,"character":8}
+                    },
+                    "severity":2,
+                    "code":"unused-variable",
+                    "source":SERVER_NAME,
+                    "message":"Unused variable `a`",
+                    "tags":[1]
+                },{
+                    "range":{
+                        "start":{"line":0,"character":12},
+                        "end":{"line":0,"character":14}
+                    },
+                    "severity":1,
+                    "source":SERVER_NAME,
+                    "message":"Unknown escape sequence `\\q`"
+                }]
+            }),
+        ));
+
+        let did_open_notification =
+            did_open_notification(uri.clone(), "severo a = \"\\q\"".to_string());
+        req_client
+            .write_all(format_request(did_open_notification).as_bytes())
+            .await
+            .unwrap();
+
+        let response = get_response_string(resp_client).await;
+        assert_outputs(expected_response, response)
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn formatting_with_project_config() {
+        let (mut req_client, resp_client) = init_lsp().await;
+        let request_id = 3;
+        let expected_response = format_response(build_response(
+            request_id,
+            Ok(json!([
+                {
+                    "range":{"start":{"line":0,"character":7},"end":{"line":0,"character":7}},
+                    "newText":" "
+                },
+                {
+                    "range":{"start":{"line":0,"character":8},"end":{"line":1,"character":0}},
+                    "newText":"\n  "
+                },
+                {
+                    "range":{"start":{"line":1,"character":1},"end":{"line":1,"character":1}},
+                    "newText":"\n"
+                }
+            ])),
+        ));
+
+        let current_dir = env::current_dir().expect("Failed to get current directory");
+        let formatting_mock = current_dir
+            .join("src/tests/mocks/formatting/nested/main.severo")
+            .to_string_lossy()
+            .to_string();
+        let formatting_request = formatting_request(request_id, formatting_mock);
+        req_client
+            .write_all(format_request(formatting_request).as_bytes())
+            .await
+            .unwrap();
+
+        let response = get_response_string(resp_client).await;
+        assert_outputs(expected_response, response)
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn run_file_without_a_file() {
+        let (mut req_client, resp_client) = init_lsp().await;
+        let request_id = 3;
+        let expected_response = format_response(build_response(
+            request_id,
+            Err(json!({
+                "code":-32602,
+                "message":MISSING_FILE_ARGUMENT
+            })),
+        ));
+
+        let request = execute_command_request(request_id, RUN_FILE_COMMAND, json!([]));
+        req_client
+            .write_all(format_request(request).as_bytes())
+            .await
+            .unwrap();
+
+        let response = get_response_string(resp_client).await;
+        assert_outputs(expected_response, response)
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn shutdown() {
         let (mut req_client, resp_client) = init_lsp().await;
@@ -0,0 +1,7 @@
+use std::io;
+
+fn main() {
+    if let Err(error) = severo_lsp::dap::serve(io::stdin().lock(), io::stdout()) {
+        eprintln!("severo-dap: {}", error);
+    }
+}
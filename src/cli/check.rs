@@ -0,0 +1,395 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Url};
+
+use crate::{
+    config::load_config,
+    consts::{SERVER_NAME, SERVER_VERSION},
+    lint::LintConfig,
+    methods::diagnostics::get_diagnostics,
+};
+
+use super::{collect_files, ERROR, FAILURE, SUCCESS};
+
+pub const CHECK_USAGE: &str = "Usage: severo-lsp check [--format human|json|sarif] [PATH...]
+
+Reports the diagnostics of Severo files. Directories are searched for
+`.severo` files and the current directory is checked by default.
+
+Exit codes:
+  0  No errors were found
+  1  Some file has errors
+  2  The files could not be read or the arguments are invalid";
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Sarif,
+}
+
+#[derive(PartialEq, Debug, Default)]
+struct CheckArgs {
+    format: OutputFormat,
+    paths: Vec<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Result<CheckArgs, String> {
+    let mut check_args = CheckArgs::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                check_args.format = match args.next().map(String::as_str) {
+                    Some("human") => OutputFormat::Human,
+                    Some("json") => OutputFormat::Json,
+                    Some("sarif") => OutputFormat::Sarif,
+                    Some(format) => return Err(format!("Unknown format `{}`", format)),
+                    None => return Err("Missing value for `--format`".to_string()),
+                }
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option `{}`", flag)),
+            path => check_args.paths.push(PathBuf::from(path)),
+        }
+    }
+    if check_args.paths.is_empty() {
+        check_args.paths.push(PathBuf::from("."));
+    }
+    Ok(check_args)
+}
+
+struct FileDiagnostics {
+    path: PathBuf,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Runs `severo-lsp check` and returns the process exit code.
+pub fn run(args: &[String], stdout: &mut impl Write, stderr: &mut impl Write) -> i32 {
+    let check_args = match parse_args(args) {
+        Ok(check_args) => check_args,
+        Err(message) => {
+            let _ = writeln!(stderr, "error: {}\n\n{}", message, CHECK_USAGE);
+            return ERROR;
+        }
+    };
+
+    let mut files = Vec::new();
+    for path in &check_args.paths {
+        if let Err(error) = collect_files(path, &mut files) {
+            let _ = writeln!(stderr, "error: {}: {}", path.display(), error);
+            return ERROR;
+        }
+    }
+
+    let mut results = Vec::new();
+    let mut unreadable = false;
+    for path in files {
+        match fs::read_to_string(&path) {
+            Ok(source) => results.push(FileDiagnostics {
+                diagnostics: get_diagnostics(
+                    &file_uri(&path),
+                    source,
+                    &load_config(&path).lint_config(&LintConfig::new()),
+                ),
+                path,
+            }),
+            Err(error) => {
+                let _ = writeln!(stderr, "error: {}: {}", path.display(), error);
+                unreadable = true;
+            }
+        }
+    }
+
+    let output = match check_args.format {
+        OutputFormat::Human => human_output(&results),
+        OutputFormat::Json => format!("{:#}\n", json_output(&results)),
+        OutputFormat::Sarif => format!("{:#}\n", sarif_output(&results)),
+    };
+    let _ = write!(stdout, "{}", output);
+
+    let has_errors = results
+        .iter()
+        .flat_map(|file| &file.diagnostics)
+        .any(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR));
+    match (unreadable, has_errors) {
+        (true, _) => ERROR,
+        (false, true) => FAILURE,
+        (false, false) => SUCCESS,
+    }
+}
+
+fn file_uri(path: &Path) -> Url {
+    let absolute = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    Url::from_file_path(absolute).unwrap_or_else(|_| Url::parse("file:///").unwrap())
+}
+
+fn severity_name(diagnostic: &Diagnostic) -> &'static str {
+    match diagnostic.severity {
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "error",
+    }
+}
+
+fn rule_id(diagnostic: &Diagnostic) -> Option<String> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => Some(code.clone()),
+        Some(NumberOrString::Number(code)) => Some(code.to_string()),
+        None => None,
+    }
+}
+
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn human_output(results: &[FileDiagnostics]) -> String {
+    let mut output = String::new();
+    let mut errors = 0;
+    let mut warnings = 0;
+    for file in results {
+        for diagnostic in &file.diagnostics {
+            match severity_name(diagnostic) {
+                "error" => errors += 1,
+                "warning" => warnings += 1,
+                _ => {}
+            }
+            let start = diagnostic.range.start;
+            output.push_str(&format!(
+                "{}:{}:{}: {}: {}",
+                display_path(&file.path),
+                start.line + 1,
+                start.character + 1,
+                severity_name(diagnostic),
+                diagnostic.message
+            ));
+            if let Some(rule) = rule_id(diagnostic) {
+                output.push_str(&format!(" [{}]", rule));
+            }
+            output.push('\n');
+        }
+    }
+    output.push_str(&format!(
+        "Checked {} file{}: {} error{}, {} warning{}\n",
+        results.len(),
+        plural(results.len()),
+        errors,
+        plural(errors),
+        warnings,
+        plural(warnings)
+    ));
+    output
+}
+
+fn plural(count: usize) -> &'static str {
+    match count {
+        1 => "",
+        _ => "s",
+    }
+}
+
+/// Lines and columns are 1-based, columns count UTF-16 code units.
+fn json_output(results: &[FileDiagnostics]) -> Value {
+    let diagnostics: Vec<Value> = results
+        .iter()
+        .flat_map(|file| {
+            file.diagnostics.iter().map(|diagnostic| {
+                let range = diagnostic.range;
+                json!({
+                    "file": display_path(&file.path),
+                    "line": range.start.line + 1,
+                    "column": range.start.character + 1,
+                    "endLine": range.end.line + 1,
+                    "endColumn": range.end.character + 1,
+                    "severity": severity_name(diagnostic),
+                    "rule": rule_id(diagnostic),
+                    "message": diagnostic.message,
+                })
+            })
+        })
+        .collect();
+    json!({
+        "files": results.len(),
+        "diagnostics": diagnostics,
+    })
+}
+
+fn sarif_output(results: &[FileDiagnostics]) -> Value {
+    let sarif_results: Vec<Value> = results
+        .iter()
+        .flat_map(|file| {
+            file.diagnostics.iter().map(|diagnostic| {
+                let range = diagnostic.range;
+                let level = match severity_name(diagnostic) {
+                    "error" => "error",
+                    "warning" => "warning",
+                    _ => "note",
+                };
+                let mut result = json!({
+                    "level": level,
+                    "message": {"text": diagnostic.message},
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {"uri": display_path(&file.path)},
+                            "region": {
+                                "startLine": range.start.line + 1,
+                                "startColumn": range.start.character + 1,
+                                "endLine": range.end.line + 1,
+                                "endColumn": range.end.character + 1,
+                            }
+                        }
+                    }]
+                });
+                if let Some(rule) = rule_id(diagnostic) {
+                    result["ruleId"] = json!(rule);
+                }
+                result
+            })
+        })
+        .collect();
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": SERVER_NAME,
+                    "version": SERVER_VERSION,
+                }
+            },
+            "columnKind": "utf16CodeUnits",
+            "results": sarif_results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("severo-check-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("valid.severo"), "print(1)\n").unwrap();
+        fs::write(dir.join("invalid.severo"), "severo a = \"\\q\"\nprint(").unwrap();
+        dir
+    }
+
+    fn run_with(arguments: &[&str]) -> (i32, String, String) {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let code = run(&args(arguments), &mut stdout, &mut stderr);
+        (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn should_parse_args() {
+        assert_eq!(
+            parse_args(&args(&[])),
+            Ok(CheckArgs {
+                format: OutputFormat::Human,
+                paths: vec![PathBuf::from(".")],
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["--format", "sarif", "src"])).map(|args| args.format),
+            Ok(OutputFormat::Sarif)
+        );
+        assert!(parse_args(&args(&["--format", "xml"])).is_err());
+        assert!(parse_args(&args(&["--format"])).is_err());
+        assert!(parse_args(&args(&["--fix"])).is_err());
+    }
+
+    #[test]
+    fn should_print_human_readable_diagnostics() {
+        let dir = temp_dir("human");
+        let (code, stdout, _) = run_with(&[dir.to_str().unwrap()]);
+        let file = display_path(&dir.join("invalid.severo"));
+        assert_eq!(code, FAILURE);
+        assert_eq!(
+            stdout,
+            format!(
+                "{0}:1:8: warning: Unused variable `a` [unused-variable]\n\
+                 {0}:1:13: error: Unknown escape sequence `\\q`\n\
+                 {0}:2:7: error: Expected `)`\n\
+                 Checked 2 files: 2 errors, 1 warning\n",
+                file
+            )
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_print_json_diagnostics() {
+        let dir = temp_dir("json");
+        let (_, stdout, _) = run_with(&["--format", "json", dir.to_str().unwrap()]);
+        let output: Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(output["files"], 2);
+        assert_eq!(
+            output["diagnostics"][1],
+            json!({
+                "file": display_path(&dir.join("invalid.severo")),
+                "line": 1,
+                "column": 13,
+                "endLine": 1,
+                "endColumn": 15,
+                "severity": "error",
+                "rule": null,
+                "message": "Unknown escape sequence `\\q`",
+            })
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_print_sarif_diagnostics() {
+        let dir = temp_dir("sarif");
+        let (_, stdout, _) = run_with(&["--format", "sarif", dir.to_str().unwrap()]);
+        let output: Value = serde_json::from_str(&stdout).unwrap();
+        let run = &output["runs"][0];
+        assert_eq!(output["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["name"], SERVER_NAME);
+        assert_eq!(run["results"].as_array().unwrap().len(), 3);
+        assert_eq!(run["results"][0]["ruleId"], "unused-variable");
+        assert_eq!(run["results"][0]["level"], "warning");
+        assert_eq!(run["results"][2]["level"], "error");
+        assert_eq!(
+            run["results"][2]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            2
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_succeed_without_errors() {
+        let dir = temp_dir("success");
+        fs::remove_file(dir.join("invalid.severo")).unwrap();
+        let (code, stdout, _) = run_with(&[dir.to_str().unwrap()]);
+        assert_eq!(code, SUCCESS);
+        assert_eq!(stdout, "Checked 1 file: 0 errors, 0 warnings\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_fail_on_missing_paths() {
+        let (code, _, stderr) = run_with(&["missing"]);
+        assert_eq!(code, ERROR);
+        assert!(stderr.starts_with("error: missing: "));
+    }
+}
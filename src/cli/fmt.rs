@@ -0,0 +1,253 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{config::load_config, spec::formatter::format_source};
+
+use super::{collect_files, ERROR, FAILURE, SUCCESS};
+
+pub const FMT_USAGE: &str = "Usage: severo-lsp fmt [--check] [--stdin] [PATH...]
+
+Formats Severo files in place. Directories are searched for `.severo` files.
+
+Options:
+  --check   Do not write anything, exit with 1 if some file would change
+  --stdin   Format the code read from stdin and print it to stdout";
+
+#[derive(PartialEq, Debug, Default)]
+struct FmtArgs {
+    check: bool,
+    stdin: bool,
+    paths: Vec<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Result<FmtArgs, String> {
+    let mut fmt_args = FmtArgs::default();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => fmt_args.check = true,
+            "--stdin" => fmt_args.stdin = true,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option `{}`", flag)),
+            path => fmt_args.paths.push(PathBuf::from(path)),
+        }
+    }
+    match (fmt_args.stdin, fmt_args.paths.is_empty()) {
+        (true, false) => Err("`--stdin` cannot be used with paths".to_string()),
+        (false, true) => Err("No files to format".to_string()),
+        _ => Ok(fmt_args),
+    }
+}
+
+/// Runs `severo-lsp fmt` and returns the process exit code.
+pub fn run(
+    args: &[String],
+    stdin: &mut impl Read,
+    stdout: &mut impl Write,
+    stderr: &mut impl Write,
+) -> i32 {
+    let fmt_args = match parse_args(args) {
+        Ok(fmt_args) => fmt_args,
+        Err(message) => {
+            let _ = writeln!(stderr, "error: {}\n\n{}", message, FMT_USAGE);
+            return ERROR;
+        }
+    };
+
+    if fmt_args.stdin {
+        return format_stdin(fmt_args.check, stdin, stdout, stderr);
+    }
+
+    let mut files = Vec::new();
+    for path in &fmt_args.paths {
+        if let Err(error) = collect_files(path, &mut files) {
+            let _ = writeln!(stderr, "error: {}: {}", path.display(), error);
+            return ERROR;
+        }
+    }
+
+    let mut exit_code = SUCCESS;
+    for file in files {
+        let code = format_file(&file, fmt_args.check, stdout, stderr);
+        exit_code = exit_code.max(code);
+    }
+    exit_code
+}
+
+fn format_stdin(
+    check: bool,
+    stdin: &mut impl Read,
+    stdout: &mut impl Write,
+    stderr: &mut impl Write,
+) -> i32 {
+    let mut source = String::new();
+    if let Err(error) = stdin.read_to_string(&mut source) {
+        let _ = writeln!(stderr, "error: <stdin>: {}", error);
+        return ERROR;
+    }
+    let options = load_config(Path::new(".")).format_options(None);
+    match format_source(&source, &options) {
+        Ok(formatted) if check => match formatted == source {
+            true => SUCCESS,
+            false => {
+                let _ = writeln!(stdout, "Would reformat <stdin>");
+                FAILURE
+            }
+        },
+        Ok(formatted) => {
+            let _ = write!(stdout, "{}", formatted);
+            SUCCESS
+        }
+        Err(error) => {
+            let _ = writeln!(stderr, "error: <stdin>: {}", error.message);
+            ERROR
+        }
+    }
+}
+
+fn format_file(path: &Path, check: bool, stdout: &mut impl Write, stderr: &mut impl Write) -> i32 {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            let _ = writeln!(stderr, "error: {}: {}", path.display(), error);
+            return ERROR;
+        }
+    };
+    let options = load_config(path).format_options(None);
+    let formatted = match format_source(&source, &options) {
+        Ok(formatted) => formatted,
+        Err(error) => {
+            let _ = writeln!(stderr, "error: {}: {}", path.display(), error.message);
+            return ERROR;
+        }
+    };
+    if formatted == source {
+        return SUCCESS;
+    }
+    if check {
+        let _ = writeln!(stdout, "Would reformat {}", path.display());
+        return FAILURE;
+    }
+    match fs::write(path, formatted) {
+        Ok(()) => {
+            let _ = writeln!(stdout, "Formatted {}", path.display());
+            SUCCESS
+        }
+        Err(error) => {
+            let _ = writeln!(stderr, "error: {}: {}", path.display(), error);
+            ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("severo-fmt-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        dir
+    }
+
+    fn run_with(arguments: &[&str], input: &str) -> (i32, String, String) {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let code = run(
+            &args(arguments),
+            &mut input.as_bytes(),
+            &mut stdout,
+            &mut stderr,
+        );
+        (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn should_parse_args() {
+        assert_eq!(
+            parse_args(&args(&["--check", "a.severo"])),
+            Ok(FmtArgs {
+                check: true,
+                stdin: false,
+                paths: vec![PathBuf::from("a.severo")],
+            })
+        );
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["--stdin", "a.severo"])).is_err());
+        assert!(parse_args(&args(&["--write"])).is_err());
+    }
+
+    #[test]
+    fn should_format_stdin() {
+        let (code, stdout, _) = run_with(&["--stdin"], "severo a=1");
+        assert_eq!(code, SUCCESS);
+        assert_eq!(stdout, "severo a = 1\n");
+
+        let (code, stdout, _) = run_with(&["--stdin", "--check"], "severo a=1");
+        assert_eq!(code, FAILURE);
+        assert_eq!(stdout, "Would reformat <stdin>\n");
+
+        let (code, _, stderr) = run_with(&["--stdin"], "print(");
+        assert_eq!(code, ERROR);
+        assert_eq!(
+            stderr,
+            "error: <stdin>: Cannot format a file with syntax errors\n"
+        );
+    }
+
+    #[test]
+    fn should_check_files_without_writing_them() {
+        let dir = temp_dir("check");
+        let unformatted = dir.join("nested/a.severo");
+        fs::write(&unformatted, "print( 1 )").unwrap();
+        fs::write(dir.join("b.severo"), "print(2)\n").unwrap();
+        fs::write(dir.join("notes.txt"), "print( 3 )").unwrap();
+
+        let (code, stdout, _) = run_with(&["--check", dir.to_str().unwrap()], "");
+        assert_eq!(code, FAILURE);
+        assert_eq!(
+            stdout,
+            format!("Would reformat {}\n", unformatted.display())
+        );
+        assert_eq!(fs::read_to_string(&unformatted).unwrap(), "print( 1 )");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_format_files_in_place() {
+        let dir = temp_dir("write");
+        let file = dir.join("nested/a.severo");
+        fs::write(&file, "print( 1 )").unwrap();
+        fs::write(dir.join("severo.json"), r#"{"format":{"tabSize":2}}"#).unwrap();
+        fs::write(dir.join("b.severo"), "while a{print(a)}").unwrap();
+
+        let (code, _, _) = run_with(&[dir.to_str().unwrap()], "");
+        assert_eq!(code, SUCCESS);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "print(1)\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("b.severo")).unwrap(),
+            "while a {\n  print(a)\n}\n"
+        );
+
+        let (code, _, _) = run_with(&["--check", dir.to_str().unwrap()], "");
+        assert_eq!(code, SUCCESS);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_fail_on_missing_files() {
+        let (code, _, stderr) = run_with(&["missing.severo"], "");
+        assert_eq!(code, ERROR);
+        assert!(stderr.starts_with("error: missing.severo: "));
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub mod check;
+pub mod fmt;
+pub mod repl;
+
+pub const SUCCESS: i32 = 0;
+pub const FAILURE: i32 = 1;
+pub const ERROR: i32 = 2;
+
+/// Collects `path` if it is a file, or the `.severo` files under it,
+/// skipping hidden directories.
+pub fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        fs::metadata(path)?;
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if entry.is_dir() {
+            if !hidden {
+                collect_files(&entry, files)?;
+            }
+        } else if entry
+            .extension()
+            .is_some_and(|extension| extension == "severo")
+        {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
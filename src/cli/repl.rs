@@ -0,0 +1,337 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use signal_hook::{consts::SIGINT, flag};
+
+use crate::{
+    consts::{SERVER_NAME, SERVER_VERSION},
+    methods::completion::get_completion_items,
+    spec::{
+        ast::Stmt,
+        interpreter::{Interpreter, Value},
+        parser::{get_vars, parse},
+        scanner::scan_tokens,
+        types::TokenType,
+    },
+};
+
+use super::{ERROR, SUCCESS};
+
+pub const REPL_USAGE: &str = "Usage: severo-lsp repl
+
+Evaluates Severo statements as they are typed. Declarations are kept for the
+next entries, and Tab completes keywords and declared names.";
+
+/// What evaluating an entry printed, and the error that stopped it.
+#[derive(PartialEq, Debug, Default)]
+pub struct Evaluation {
+    pub output: String,
+    pub error: Option<String>,
+}
+
+/// Evaluates Severo code entry by entry, keeping the declarations of the
+/// earlier entries.
+pub struct Repl {
+    interpreter: Interpreter,
+    /// The entries evaluated so far, for completion.
+    source: String,
+    /// Set, e.g. by Ctrl-C, to interrupt the entry being evaluated.
+    stop: Arc<AtomicBool>,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        Repl {
+            interpreter: Interpreter::new().with_stop(stop.clone()),
+            source: String::new(),
+            stop,
+        }
+    }
+}
+
+impl Repl {
+    /// Evaluates an entry. A single expression also prints its value,
+    /// unless it is `nil`.
+    pub fn eval(&mut self, input: &str) -> Evaluation {
+        let parse = parse(input);
+        let syntax_error = scan_tokens(input.to_string())
+            .errors
+            .into_iter()
+            .map(|error| error.message)
+            .chain(parse.errors.iter().map(|error| error.message.clone()))
+            .next();
+        if let Some(error) = syntax_error {
+            return Evaluation {
+                output: String::new(),
+                error: Some(error),
+            };
+        }
+        self.source.push_str(input);
+        self.source.push('\n');
+        self.stop.store(false, Ordering::Relaxed);
+
+        let file = parse.tree();
+        let statements: Vec<Stmt> = file.statements().collect();
+        let result = match statements.as_slice() {
+            [Stmt::ExprStmt(statement)] if statement.expr().is_some() => {
+                let globals = self.interpreter.globals();
+                let expr = statement.expr().expect("an expression");
+                self.interpreter.evaluate(&expr, &globals).map(Some)
+            }
+            _ => self.interpreter.run(&file).map(|()| None),
+        };
+        let mut output = std::mem::take(&mut self.interpreter.output);
+        match result {
+            Ok(Some(value)) if value != Value::Nil => {
+                output.push_str(&format!("{:?}\n", value));
+                Evaluation {
+                    output,
+                    error: None,
+                }
+            }
+            Ok(_) => Evaluation {
+                output,
+                error: None,
+            },
+            Err(_) if self.stop.load(Ordering::Relaxed) => Evaluation {
+                output,
+                error: Some("Interrupted".to_string()),
+            },
+            Err(error) => Evaluation {
+                output,
+                error: Some(error.message),
+            },
+        }
+    }
+}
+
+/// Whether `input` opens more braces or parentheses than it closes, so the
+/// entry goes on in the next line.
+pub fn is_incomplete(input: &str) -> bool {
+    let depth: i32 = scan_tokens(input.to_string())
+        .tokens
+        .iter()
+        .map(|token| match token.token_type {
+            TokenType::LeftBrace | TokenType::LeftParen => 1,
+            TokenType::RightBrace | TokenType::RightParen => -1,
+            _ => 0,
+        })
+        .sum();
+    depth > 0
+}
+
+/// Completes the word before `pos` in `line` like the editor does, with the
+/// variables declared in `source` and `line`. Returns where the word starts.
+pub fn complete(source: &str, line: &str, pos: usize) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(pos, |(index, _)| index);
+    let variables = get_vars(scan_tokens(format!("{}{}", source, line)).tokens);
+    let mut names: Vec<String> = Vec::new();
+    for item in get_completion_items(line[start..pos].to_string(), variables).unwrap_or_default() {
+        if !names.contains(&item.label) {
+            names.push(item.label);
+        }
+    }
+    (start, names)
+}
+
+/// Gives the line editor the completions of the entries evaluated so far.
+#[derive(Default)]
+struct ReplHelper {
+    source: String,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, names) = complete(&self.source, line, pos);
+        let candidates = names
+            .into_iter()
+            .map(|name| Pair {
+                display: name.clone(),
+                replacement: name,
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Runs `severo-lsp repl` until the input ends and returns the process exit
+/// code.
+pub fn run(args: &[String], stdout: &mut impl Write, stderr: &mut impl Write) -> i32 {
+    if let Some(arg) = args.first() {
+        let _ = writeln!(
+            stderr,
+            "error: Unknown argument `{}`\n\n{}",
+            arg, REPL_USAGE
+        );
+        return ERROR;
+    }
+    let mut editor = match Editor::<ReplHelper, DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(error) => {
+            let _ = writeln!(stderr, "error: {}", error);
+            return ERROR;
+        }
+    };
+    editor.set_helper(Some(ReplHelper::default()));
+    let _ = writeln!(
+        stdout,
+        "{} {} REPL. Press Ctrl-D to exit.",
+        SERVER_NAME, SERVER_VERSION
+    );
+
+    let mut repl = Repl::default();
+    // Ctrl-C interrupts the entry being evaluated. While reading a line,
+    // the editor gets it as a key instead, unless the input is not a
+    // terminal, where it ends the REPL as usual.
+    let idle = Arc::new(AtomicBool::new(true));
+    let handlers = flag::register_conditional_default(SIGINT, idle.clone())
+        .and_then(|_| flag::register(SIGINT, repl.stop.clone()));
+    if let Err(error) = handlers {
+        let _ = writeln!(stderr, "error: {}", error);
+        return ERROR;
+    }
+    let mut input = String::new();
+    loop {
+        let prompt = match input.is_empty() {
+            true => "> ",
+            false => "... ",
+        };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                input.push_str(&line);
+                input.push('\n');
+                if is_incomplete(&input) {
+                    continue;
+                }
+                let entry = std::mem::take(&mut input);
+                if entry.trim().is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(entry.trim_end());
+                idle.store(false, Ordering::Relaxed);
+                let evaluation = repl.eval(&entry);
+                idle.store(true, Ordering::Relaxed);
+                let _ = write!(stdout, "{}", evaluation.output);
+                if let Some(error) = evaluation.error {
+                    let _ = writeln!(stderr, "error: {}", error);
+                }
+                if let Some(helper) = editor.helper_mut() {
+                    helper.source = repl.source.clone();
+                }
+            }
+            // Ctrl-C drops the entry being typed.
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(ReadlineError::Eof) => return SUCCESS,
+            Err(error) => {
+                let _ = writeln!(stderr, "error: {}", error);
+                return ERROR;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(output: &str) -> Evaluation {
+        Evaluation {
+            output: output.to_string(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn should_keep_bindings_across_entries() {
+        let mut repl = Repl::default();
+        assert_eq!(repl.eval("severo name = \"Ana\"\n"), output(""));
+        assert_eq!(
+            repl.eval("severo greet = fun(who) {\n    return \"Hi \" + who\n}\n"),
+            output("")
+        );
+        assert_eq!(repl.eval("greet(name)\n"), output("\"Hi Ana\"\n"));
+        assert_eq!(repl.eval("print(1 + 2)\n"), output("3\n"));
+    }
+
+    #[test]
+    fn should_report_errors_and_keep_what_ran() {
+        let mut repl = Repl::default();
+        assert_eq!(
+            repl.eval("severo a = 1\nprint(a)\nprint(b)\n"),
+            Evaluation {
+                output: "1\n".to_string(),
+                error: Some("Undefined variable `b`".to_string()),
+            }
+        );
+        assert_eq!(repl.eval("a\n"), output("1\n"));
+        assert_eq!(
+            repl.eval("print(\n").error,
+            Some("Expected `)`".to_string())
+        );
+    }
+
+    #[test]
+    fn should_interrupt_endless_entries() {
+        let mut repl = Repl::default();
+        let stop = repl.stop.clone();
+        let interrupt = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            stop.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(
+            repl.eval("while true {}\n").error,
+            Some("Interrupted".to_string())
+        );
+        interrupt.join().unwrap();
+        assert_eq!(repl.eval("1 + 1\n"), output("2\n"));
+    }
+
+    #[test]
+    fn should_wait_for_unclosed_blocks() {
+        assert!(is_incomplete("severo f = fun() {\n"));
+        assert!(is_incomplete("print(\n"));
+        assert!(!is_incomplete("severo f = fun() {\n}\n"));
+    }
+
+    #[test]
+    fn should_complete_keywords_and_declared_names() {
+        let source = "severo total = 1\nsevero tally = 2\n";
+        assert_eq!(
+            complete(source, "print(ta", 8),
+            (6, vec!["tally".to_string()])
+        );
+        assert_eq!(complete(source, "to", 2), (0, vec!["total".to_string()]));
+        assert_eq!(complete("", "sev", 3), (0, vec!["severo".to_string()]));
+    }
+}
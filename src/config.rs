@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tower_lsp::lsp_types::{FormattingOptions, Url};
+
+use crate::{lint::LintConfig, spec::formatter::FormatOptions};
+
+pub const CONFIG_FILE_NAME: &str = "severo.json";
+
+/// Project settings read from the nearest `severo.json`.
+#[derive(Deserialize, PartialEq, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    pub format: FormatConfig,
+    pub lint: LintConfig,
+}
+
+#[derive(Deserialize, PartialEq, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FormatConfig {
+    pub tab_size: Option<u32>,
+    pub insert_spaces: Option<bool>,
+    pub max_blank_lines: Option<usize>,
+    pub normalize_strings: Option<bool>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        serde_json::from_str(text).map_err(|error| error.to_string())
+    }
+
+    /// Project settings take precedence over the editor ones.
+    pub fn format_options(&self, editor: Option<&FormattingOptions>) -> FormatOptions {
+        let mut options = FormatOptions::default();
+        if let Some(editor) = editor {
+            options.tab_size = editor.tab_size;
+            options.insert_spaces = editor.insert_spaces;
+        }
+        let format = &self.format;
+        options.tab_size = format.tab_size.unwrap_or(options.tab_size);
+        options.insert_spaces = format.insert_spaces.unwrap_or(options.insert_spaces);
+        options.max_blank_lines = format.max_blank_lines.unwrap_or(options.max_blank_lines);
+        options.normalize_strings = format
+            .normalize_strings
+            .unwrap_or(options.normalize_strings);
+        options
+    }
+
+    /// Rule levels from the editor settings, overridden by the project ones.
+    pub fn lint_config(&self, editor: &LintConfig) -> LintConfig {
+        let mut config = editor.clone();
+        config.extend(self.lint.clone());
+        config
+    }
+}
+
+pub fn find_config_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|directory| directory.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Loads the config that applies to `path`, falling back to the defaults when
+/// there is none or it cannot be read.
+pub fn load_config(path: &Path) -> Config {
+    let directory = match path.is_dir() {
+        true => path,
+        false => path.parent().unwrap_or(path),
+    };
+    find_config_file(directory)
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|text| Config::parse(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn load_config_for(uri: &Url) -> Config {
+    match uri.to_file_path() {
+        Ok(path) => load_config(&path),
+        Err(()) => Config::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::lint::RuleLevel;
+
+    use super::*;
+
+    #[test]
+    fn should_parse_partial_config() {
+        let config = Config::parse(r#"{"format":{"tabSize":2}}"#).unwrap();
+        assert_eq!(config.format.tab_size, Some(2));
+        assert_eq!(config.format.insert_spaces, None);
+        assert_eq!(Config::parse("{}").unwrap(), Config::default());
+        assert!(Config::parse("{").is_err());
+    }
+
+    #[test]
+    fn should_prefer_project_settings_over_editor_ones() {
+        let config = Config::parse(r#"{"format":{"tabSize":2,"maxBlankLines":0}}"#).unwrap();
+        let editor = FormattingOptions {
+            tab_size: 8,
+            insert_spaces: false,
+            ..Default::default()
+        };
+        let options = config.format_options(Some(&editor));
+        assert_eq!(options.tab_size, 2);
+        assert!(!options.insert_spaces);
+        assert_eq!(options.max_blank_lines, 0);
+        assert!(options.normalize_strings);
+    }
+
+    #[test]
+    fn should_merge_lint_levels() {
+        let config = Config::parse(r#"{"lint":{"unused-variable":"error"}}"#).unwrap();
+        let editor = LintConfig::from([
+            ("unused-variable".to_string(), RuleLevel::Off),
+            ("empty-print".to_string(), RuleLevel::Off),
+        ]);
+        let lint_config = config.lint_config(&editor);
+        assert_eq!(lint_config["unused-variable"], RuleLevel::Error);
+        assert_eq!(lint_config["empty-print"], RuleLevel::Off);
+        assert!(Config::parse(r#"{"lint":{"empty-print":"loud"}}"#).is_err());
+    }
+
+    #[test]
+    fn should_find_the_nearest_config_file() {
+        let current_dir = env::current_dir().expect("Failed to get current directory");
+        let mocks = current_dir.join("src/tests/mocks/formatting");
+        let config = load_config(&mocks.join("nested/main.severo"));
+        assert_eq!(config.format.tab_size, Some(2));
+        assert_eq!(load_config(&current_dir.join("src")), Config::default());
+    }
+
+    #[test]
+    fn should_default_for_uris_without_a_file() {
+        let untitled = Url::parse("untitled:Untitled-1").unwrap();
+        assert_eq!(load_config_for(&untitled), Config::default());
+        let not_utf8 = Url::parse("file:///%FF/main.severo").unwrap();
+        assert_eq!(load_config_for(&not_utf8), Config::default());
+    }
+}
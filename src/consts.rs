@@ -1,2 +1,4 @@
 pub const SERVER_NAME: &str = "severo-lsp";
 pub const SERVER_VERSION: &str = "0.1.0";
+pub const RUN_FILE_COMMAND: &str = "severo.runFile";
+pub const SHOW_REFERENCES_COMMAND: &str = "severo.showReferences";
@@ -0,0 +1,328 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value as Json};
+
+use super::protocol::{Request, Sender};
+use crate::spec::{
+    ast::Stmt,
+    interpreter::{Debugger, Environment, Frame, Instance, Interpreter, Value},
+    line_index::LineIndex,
+    parser::parse,
+    syntax::TextRange,
+};
+
+/// The lines with a breakpoint, counted from 0, by file.
+pub type Breakpoints = Arc<Mutex<HashMap<PathBuf, HashSet<usize>>>>;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Step {
+    Continue,
+    Over,
+    In,
+    Out,
+}
+
+/// What the session asks of the paused program.
+pub enum Command {
+    Resume(Step),
+    /// A request about the paused state, answered by the program's thread
+    /// since the values live there.
+    Inspect(Request),
+}
+
+/// Whether the client counts lines and columns from 1.
+#[derive(Clone, Copy)]
+pub struct Positions {
+    pub lines_start_at1: bool,
+    pub columns_start_at1: bool,
+}
+
+pub struct Program {
+    pub path: PathBuf,
+    pub source: String,
+    pub stop_on_entry: bool,
+    pub positions: Positions,
+    pub breakpoints: Breakpoints,
+    pub pause: Arc<AtomicBool>,
+    pub stop: Arc<AtomicBool>,
+    pub commands: Receiver<Command>,
+    pub sender: Sender,
+}
+
+/// Runs the program, pausing it as the session asks, and tells the client
+/// when it ends.
+pub fn run(program: Program) {
+    let parse = parse(&program.source);
+    let sender = program.sender.clone();
+    let stop = program.stop.clone();
+    let line_index = LineIndex::new(&program.source);
+    let printer = sender.clone();
+    let result = Interpreter::new()
+        .with_printer(move |line| output(&printer, "stdout", line))
+        .with_stop(stop.clone())
+        .with_debugger(Session::new(program))
+        .run(&parse.tree());
+    let exit_code = match result {
+        Ok(()) => 0,
+        Err(error) => {
+            if !stop.load(Ordering::Relaxed) {
+                let line = line_index.location(error.range.start).line + 1;
+                let message = format!("Line {}: {}", line, error.message);
+                output(&sender, "stderr", &message);
+            }
+            1
+        }
+    };
+    sender.event("exited", json!({ "exitCode": exit_code }));
+    sender.event("terminated", json!({}));
+}
+
+fn output(sender: &Sender, category: &str, line: &str) {
+    sender.event(
+        "output",
+        json!({ "category": category, "output": format!("{}\n", line) }),
+    );
+}
+
+/// A statement being run, by the depth of its frame and its range.
+#[derive(Clone, Copy)]
+struct Run {
+    depth: usize,
+    range: TextRange,
+}
+
+/// A value the client can expand in the variables view.
+enum Container {
+    Scope(Environment),
+    Instance(Rc<RefCell<Instance>>),
+}
+
+struct Session {
+    program: Program,
+    line_index: LineIndex,
+    entry: bool,
+    /// The step being taken, with the statement it started from.
+    step: Option<(Step, Run)>,
+    /// The statement that ran last.
+    last: Option<Run>,
+    /// What the variables references handed out since the program paused
+    /// point to, at their index minus 1.
+    containers: Vec<Container>,
+}
+
+impl Session {
+    fn new(program: Program) -> Self {
+        Session {
+            line_index: LineIndex::new(&program.source),
+            entry: program.stop_on_entry,
+            step: None,
+            last: None,
+            containers: Vec::new(),
+            program,
+        }
+    }
+
+    fn line(&self, range: TextRange) -> usize {
+        self.line_index.location(range.start).line
+    }
+
+    /// Whether `run` is part of the line `from` runs, i.e. a statement
+    /// inside it that starts on the same line, like the body of
+    /// `while i < 3 { i = i + 1 }`. Running the same statement again,
+    /// e.g. in the next iteration, is not.
+    fn continues(&self, from: Run, run: Run) -> bool {
+        run.depth == from.depth
+            && from.range != run.range
+            && from.range.contains_range(run.range)
+            && self.line(from.range) == self.line(run.range)
+    }
+
+    fn has_breakpoint(&self, line: usize) -> bool {
+        let breakpoints = self.program.breakpoints.lock().expect("breakpoints");
+        breakpoints
+            .get(&self.program.path)
+            .is_some_and(|lines| lines.contains(&line))
+    }
+
+    fn pause(&mut self, reason: &str, frames: &[Frame], run: Run) {
+        self.program.sender.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": 1, "allThreadsStopped": true }),
+        );
+        loop {
+            match self.program.commands.recv() {
+                Ok(Command::Resume(step)) => {
+                    self.containers.clear();
+                    self.step = (step != Step::Continue).then_some((step, run));
+                    return;
+                }
+                Ok(Command::Inspect(request)) => {
+                    let result = self.inspect(&request, frames);
+                    self.program.sender.respond(&request, result);
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn inspect(&mut self, request: &Request, frames: &[Frame]) -> Result<Json, String> {
+        let arguments = &request.arguments;
+        match request.command.as_str() {
+            "stackTrace" => {
+                let stack_frames: Vec<Json> = frames
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(id, frame)| self.stack_frame(id, frame))
+                    .collect();
+                Ok(json!({ "stackFrames": stack_frames, "totalFrames": frames.len() }))
+            }
+            "scopes" => {
+                let frame = frame(frames, &arguments["frameId"])?;
+                let mut scopes = Vec::new();
+                let mut env = Some(frame.env.clone());
+                while let Some(scope) = env {
+                    env = scope.parent();
+                    let name = match (&env, scopes.is_empty()) {
+                        (None, _) => "Globals",
+                        (Some(_), true) => "Locals",
+                        (Some(_), false) => "Enclosing",
+                    };
+                    if env.is_some() && scope.bindings().is_empty() {
+                        continue;
+                    }
+                    let reference = self.reference(Container::Scope(scope));
+                    scopes.push(json!({
+                        "name": name,
+                        "variablesReference": reference,
+                        "expensive": false,
+                    }));
+                }
+                Ok(json!({ "scopes": scopes }))
+            }
+            "variables" => {
+                let reference = arguments["variablesReference"].as_u64().unwrap_or(0) as usize;
+                let bindings = match self.containers.get(reference.wrapping_sub(1)) {
+                    Some(Container::Scope(env)) => env.bindings(),
+                    Some(Container::Instance(instance)) => instance.borrow().fields(),
+                    None => return Err("Unknown variables reference".to_string()),
+                };
+                let variables: Vec<Json> = bindings
+                    .into_iter()
+                    .filter(|(_, value)| !matches!(value, Value::Builtin(_)))
+                    .map(|(name, value)| {
+                        let mut variable = self.describe(&value);
+                        variable["name"] = json!(name);
+                        variable
+                    })
+                    .collect();
+                Ok(json!({ "variables": variables }))
+            }
+            "evaluate" => {
+                let frame = match &arguments["frameId"] {
+                    Json::Null => frames.last().ok_or("No frame to evaluate in")?,
+                    id => frame(frames, id)?,
+                };
+                let expression = arguments["expression"].as_str().unwrap_or_default();
+                let parse = parse(expression);
+                let expr = match parse.tree().statements().next() {
+                    Some(Stmt::ExprStmt(statement)) if parse.errors.is_empty() => statement.expr(),
+                    _ => None,
+                }
+                .ok_or("Expected an expression")?;
+                let printer = self.program.sender.clone();
+                let value = Interpreter::new()
+                    .with_printer(move |line| output(&printer, "stdout", line))
+                    .evaluate(&expr, &frame.env)
+                    .map_err(|error| error.message)?;
+                let mut result = self.describe(&value);
+                result["result"] = result["value"].take();
+                Ok(result)
+            }
+            command => Err(format!("Unsupported request `{}`", command)),
+        }
+    }
+
+    fn stack_frame(&self, id: usize, frame: &Frame) -> Json {
+        let location = self.line_index.location(frame.range.start);
+        let positions = self.program.positions;
+        let name = self
+            .program
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        json!({
+            "id": id,
+            "name": frame.name,
+            "line": location.line + positions.lines_start_at1 as usize,
+            "column": location.col + positions.columns_start_at1 as usize,
+            "source": { "name": name, "path": self.program.path },
+        })
+    }
+
+    fn reference(&mut self, container: Container) -> usize {
+        self.containers.push(container);
+        self.containers.len()
+    }
+
+    /// The value, type and variables reference of a value.
+    fn describe(&mut self, value: &Value) -> Json {
+        let reference = match value {
+            Value::Instance(instance) => self.reference(Container::Instance(instance.clone())),
+            _ => 0,
+        };
+        json!({
+            "value": format!("{:?}", value),
+            "type": value.type_name(),
+            "variablesReference": reference,
+        })
+    }
+}
+
+fn frame<'a>(frames: &'a [Frame], id: &Json) -> Result<&'a Frame, String> {
+    id.as_u64()
+        .and_then(|id| frames.get(id as usize))
+        .ok_or_else(|| "Unknown frame".to_string())
+}
+
+impl Debugger for Session {
+    fn statement(&mut self, frames: &[Frame]) {
+        let Some(frame) = frames.last() else {
+            return;
+        };
+        let run = Run {
+            depth: frames.len(),
+            range: frame.range,
+        };
+        let stepped = self.step.is_some_and(|(step, from)| match step {
+            Step::Continue => false,
+            Step::Over => {
+                run.depth < from.depth || (run.depth == from.depth && !self.continues(from, run))
+            }
+            Step::In => !self.continues(from, run),
+            Step::Out => run.depth < from.depth,
+        });
+        let continued = self.last.is_some_and(|last| self.continues(last, run));
+        let reason = if std::mem::take(&mut self.entry) {
+            Some("entry")
+        } else if self.program.pause.swap(false, Ordering::Relaxed) {
+            Some("pause")
+        } else if stepped {
+            Some("step")
+        } else if !continued && self.has_breakpoint(self.line(run.range)) {
+            Some("breakpoint")
+        } else {
+            None
+        };
+        self.last = Some(run);
+        if let Some(reason) = reason {
+            self.pause(reason, frames, run);
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! A Debug Adapter Protocol server that runs Severo programs with the
+//! interpreter.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender as CommandSender};
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::spec::{
+    ast::{AstNode, Stmt},
+    line_index::LineIndex,
+    parser::parse,
+};
+use debugger::{Breakpoints, Command, Positions, Program, Step};
+use protocol::{read_message, Request, Sender};
+
+pub mod debugger;
+pub mod protocol;
+
+/// The program to run, from the `launch` request.
+struct Launch {
+    path: PathBuf,
+    source: String,
+    stop_on_entry: bool,
+}
+
+struct Session {
+    sender: Sender,
+    positions: Positions,
+    breakpoints: Breakpoints,
+    launch: Option<Launch>,
+    configured: bool,
+    pause: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    /// Commands for the thread running the program, once it started.
+    commands: Option<CommandSender<Command>>,
+}
+
+/// Serves requests from `input` until the client disconnects.
+pub fn serve(mut input: impl BufRead, output: impl Write + Send + 'static) -> io::Result<()> {
+    let mut session = Session {
+        sender: Sender::new(output),
+        positions: Positions {
+            lines_start_at1: true,
+            columns_start_at1: true,
+        },
+        breakpoints: Breakpoints::default(),
+        launch: None,
+        configured: false,
+        pause: Arc::new(AtomicBool::new(false)),
+        stop: Arc::new(AtomicBool::new(false)),
+        commands: None,
+    };
+    while let Some(message) = read_message(&mut input)? {
+        let Ok(request) = serde_json::from_value::<Request>(message) else {
+            continue;
+        };
+        if !session.handle(request) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Session {
+    /// Handles a request, returning whether to keep serving.
+    fn handle(&mut self, request: Request) -> bool {
+        let arguments = &request.arguments;
+        let result = match request.command.as_str() {
+            "initialize" => {
+                self.positions = Positions {
+                    lines_start_at1: arguments["linesStartAt1"].as_bool().unwrap_or(true),
+                    columns_start_at1: arguments["columnsStartAt1"].as_bool().unwrap_or(true),
+                };
+                self.sender.respond(
+                    &request,
+                    Ok(json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                        "supportsTerminateRequest": true,
+                    })),
+                );
+                self.sender.event("initialized", json!({}));
+                return true;
+            }
+            "launch" => self.launch(arguments),
+            "setBreakpoints" => Ok(self.set_breakpoints(arguments)),
+            "configurationDone" => {
+                self.configured = true;
+                Ok(Value::Null)
+            }
+            "threads" => Ok(json!({ "threads": [{ "id": 1, "name": "main" }] })),
+            "continue" => return self.resume(&request, Step::Continue),
+            "next" => return self.resume(&request, Step::Over),
+            "stepIn" => return self.resume(&request, Step::In),
+            "stepOut" => return self.resume(&request, Step::Out),
+            "pause" => {
+                self.pause.store(true, Ordering::Relaxed);
+                Ok(Value::Null)
+            }
+            "stackTrace" | "scopes" | "variables" | "evaluate" => {
+                let sent = self.commands.as_ref().is_some_and(|commands| {
+                    commands.send(Command::Inspect(request.clone())).is_ok()
+                });
+                match sent {
+                    true => return true,
+                    false => Err("The program is not running".to_string()),
+                }
+            }
+            "disconnect" | "terminate" => {
+                self.stop.store(true, Ordering::Relaxed);
+                self.sender.respond(&request, Ok(Value::Null));
+                if let Some(commands) = &self.commands {
+                    let _ = commands.send(Command::Resume(Step::Continue));
+                }
+                return request.command == "terminate";
+            }
+            command => Err(format!("Unsupported request `{}`", command)),
+        };
+        self.sender.respond(&request, result);
+        self.start();
+        true
+    }
+
+    fn launch(&mut self, arguments: &Value) -> Result<Value, String> {
+        let Some(path) = arguments["program"].as_str() else {
+            return Err("Expected the `program` to run".to_string());
+        };
+        let source = fs::read_to_string(path).map_err(|_| format!("Cannot read `{}`", path))?;
+        if !parse(&source).errors.is_empty() {
+            return Err(format!("`{}` has syntax errors", path));
+        }
+        self.launch = Some(Launch {
+            path: canonical_path(path),
+            source,
+            stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+        });
+        Ok(Value::Null)
+    }
+
+    /// Replaces the breakpoints of a file. Only lines where a statement
+    /// starts are verified, since the program can only pause there.
+    fn set_breakpoints(&mut self, arguments: &Value) -> Value {
+        let path = canonical_path(arguments["source"]["path"].as_str().unwrap_or_default());
+        let first_line = self.positions.lines_start_at1 as usize;
+        let lines: Vec<usize> = arguments["breakpoints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .map(|line| (line as usize).saturating_sub(first_line))
+            .collect();
+        let statement_lines = statement_lines(&path);
+        let breakpoints: Vec<Value> = lines
+            .iter()
+            .map(|line| {
+                json!({
+                    "verified": statement_lines.contains(line),
+                    "line": line + first_line,
+                })
+            })
+            .collect();
+        self.breakpoints
+            .lock()
+            .expect("breakpoints")
+            .insert(path, lines.into_iter().collect());
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Answers a request to resume the program before resuming it, so the
+    /// client gets the response before the program pauses again.
+    fn resume(&self, request: &Request, step: Step) -> bool {
+        let Some(commands) = &self.commands else {
+            let error = "The program is not running".to_string();
+            self.sender.respond(request, Err(error));
+            return true;
+        };
+        let body = match step {
+            Step::Continue => json!({ "allThreadsContinued": true }),
+            _ => Value::Null,
+        };
+        self.sender.respond(request, Ok(body));
+        let _ = commands.send(Command::Resume(step));
+        true
+    }
+
+    /// Starts the program once it is launched and the client has sent its
+    /// breakpoints.
+    fn start(&mut self) {
+        if self.commands.is_some() || !self.configured {
+            return;
+        }
+        let Some(launch) = self.launch.take() else {
+            return;
+        };
+        let (commands, receiver) = channel();
+        self.commands = Some(commands);
+        let program = Program {
+            path: launch.path,
+            source: launch.source,
+            stop_on_entry: launch.stop_on_entry,
+            positions: self.positions,
+            breakpoints: self.breakpoints.clone(),
+            pause: self.pause.clone(),
+            stop: self.stop.clone(),
+            commands: receiver,
+            sender: self.sender.clone(),
+        };
+        thread::spawn(move || debugger::run(program));
+    }
+}
+
+/// Resolves `path` so breakpoints match the program however the client
+/// spells either.
+fn canonical_path(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// The lines of `path` where a statement starts.
+fn statement_lines(path: &PathBuf) -> Vec<usize> {
+    let Ok(source) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let line_index = LineIndex::new(&source);
+    parse(&source)
+        .syntax()
+        .descendants()
+        .filter(|node| Stmt::can_cast(node.kind()))
+        .map(|node| line_index.location(node.text_range().start).line)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::io::{pipe, BufReader, PipeReader, PipeWriter};
+
+    use super::*;
+
+    /// A scripted client talking to a server on another thread.
+    struct Client {
+        input: PipeWriter,
+        output: BufReader<PipeReader>,
+        seq: i64,
+    }
+
+    impl Client {
+        fn start() -> Client {
+            let (server_input, input) = pipe().unwrap();
+            let (output, server_output) = pipe().unwrap();
+            thread::spawn(move || serve(BufReader::new(server_input), server_output));
+            Client {
+                input,
+                output: BufReader::new(output),
+                seq: 0,
+            }
+        }
+
+        /// Sends a request and returns its response.
+        fn request(&mut self, command: &str, arguments: Value) -> Value {
+            self.seq += 1;
+            let body = json!({
+                "seq": self.seq,
+                "type": "request",
+                "command": command,
+                "arguments": arguments,
+            })
+            .to_string();
+            write!(self.input, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            let seq = self.seq;
+            self.wait(|message| message["request_seq"] == seq)
+        }
+
+        /// Reads messages until one matches.
+        fn wait(&mut self, matches: impl Fn(&Value) -> bool) -> Value {
+            loop {
+                let message = read_message(&mut self.output).unwrap().unwrap();
+                if matches(&message) {
+                    return message;
+                }
+            }
+        }
+
+        fn event(&mut self, event: &str) -> Value {
+            self.wait(|message| message["event"] == event)
+        }
+    }
+
+    #[test]
+    fn should_debug_a_program() {
+        let path = env::temp_dir().join("severo-dap-debug.severo");
+        let source = concat!(
+            "severo double = fun(n) {\n",
+            "    severo result = n * 2\n",
+            "    return result\n",
+            "}\n",
+            "severo a = double(21)\n",
+            "print(a)\n",
+        );
+        fs::write(&path, source).unwrap();
+
+        let mut client = Client::start();
+        let response = client.request("initialize", json!({ "adapterID": "severo" }));
+        assert_eq!(response["body"]["supportsConfigurationDoneRequest"], true);
+        client.event("initialized");
+        let response = client.request("launch", json!({ "program": path }));
+        assert_eq!(response["success"], true);
+        let response = client.request(
+            "setBreakpoints",
+            json!({ "source": { "path": path }, "breakpoints": [{ "line": 2 }, { "line": 4 }] }),
+        );
+        assert_eq!(
+            response["body"]["breakpoints"],
+            json!([{ "verified": true, "line": 2 }, { "verified": false, "line": 4 }])
+        );
+        client.request("configurationDone", json!({}));
+        assert_eq!(client.event("stopped")["body"]["reason"], "breakpoint");
+
+        let response = client.request("stackTrace", json!({ "threadId": 1 }));
+        let frames = response["body"]["stackFrames"].as_array().unwrap();
+        let frames: Vec<(&Value, &Value, &Value)> = frames
+            .iter()
+            .map(|frame| (&frame["id"], &frame["name"], &frame["line"]))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (&json!(1), &json!("double"), &json!(2)),
+                (&json!(0), &json!("main"), &json!(5)),
+            ]
+        );
+
+        let response = client.request("scopes", json!({ "frameId": 1 }));
+        let scopes = &response["body"]["scopes"];
+        assert_eq!(scopes[0]["name"], "Locals");
+        assert_eq!(scopes[1]["name"], "Globals");
+        let reference = scopes[0]["variablesReference"].clone();
+        let response = client.request("variables", json!({ "variablesReference": reference }));
+        assert_eq!(
+            response["body"]["variables"],
+            json!([{ "name": "n", "value": "21", "type": "number", "variablesReference": 0 }])
+        );
+        let response = client.request("evaluate", json!({ "expression": "n * 3", "frameId": 1 }));
+        assert_eq!(response["body"]["result"], "63");
+
+        client.request("next", json!({ "threadId": 1 }));
+        assert_eq!(client.event("stopped")["body"]["reason"], "step");
+        let response = client.request("stackTrace", json!({ "threadId": 1 }));
+        assert_eq!(response["body"]["stackFrames"][0]["line"], 3);
+
+        client.request("stepOut", json!({ "threadId": 1 }));
+        client.event("stopped");
+        let response = client.request("stackTrace", json!({ "threadId": 1 }));
+        assert_eq!(response["body"]["stackFrames"][0]["line"], 6);
+
+        client.request("continue", json!({ "threadId": 1 }));
+        assert_eq!(client.event("output")["body"]["output"], "42\n");
+        assert_eq!(client.event("exited")["body"]["exitCode"], 0);
+        client.event("terminated");
+        let response = client.request("disconnect", json!({}));
+        assert_eq!(response["success"], true);
+    }
+
+    #[test]
+    fn should_stop_every_time_a_breakpoint_runs() {
+        let path = env::temp_dir().join("severo-dap-loop.severo");
+        let source = "severo i = 0\nwhile i < 3 {\n    i = i + 1\n}\nprint(i)\n";
+        fs::write(&path, source).unwrap();
+        // The client may spell the path differently than the program's.
+        let breakpoint_path = env::temp_dir().join(".").join("severo-dap-loop.severo");
+
+        let mut client = Client::start();
+        client.request("initialize", json!({}));
+        client.request("launch", json!({ "program": path }));
+        client.request(
+            "setBreakpoints",
+            json!({ "source": { "path": breakpoint_path }, "breakpoints": [{ "line": 3 }] }),
+        );
+        client.request("configurationDone", json!({}));
+        for _ in 0..3 {
+            assert_eq!(client.event("stopped")["body"]["reason"], "breakpoint");
+            client.request("continue", json!({ "threadId": 1 }));
+        }
+        let message =
+            client.wait(|message| message["event"] == "stopped" || message["event"] == "output");
+        assert_eq!(message["body"]["output"], "3\n");
+    }
+
+    #[test]
+    fn should_refuse_programs_that_cannot_be_read() {
+        let mut client = Client::start();
+        client.request("initialize", json!({}));
+        let response = client.request("launch", json!({ "program": "/missing.severo" }));
+        assert_eq!(response["success"], false);
+        assert_eq!(response["message"], "Cannot read `/missing.severo`");
+    }
+}
@@ -0,0 +1,110 @@
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// Reads a `Content-Length` framed message, or `None` at the end of `input`.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing Content-Length header",
+        ));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+struct Writer {
+    output: Box<dyn Write + Send>,
+    seq: i64,
+}
+
+/// Writes numbered responses and events. Clones share the output, so the
+/// thread running the program can write too.
+#[derive(Clone)]
+pub struct Sender(Arc<Mutex<Writer>>);
+
+impl Sender {
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        Sender(Arc::new(Mutex::new(Writer {
+            output: Box::new(output),
+            seq: 0,
+        })))
+    }
+
+    pub fn respond(&self, request: &Request, result: Result<Value, String>) {
+        let mut message = json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) if !body.is_null() => message["body"] = body,
+            Ok(_) => {}
+            Err(error) => message["message"] = json!(error),
+        }
+        self.send(message);
+    }
+
+    pub fn event(&self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn send(&self, mut message: Value) {
+        let mut writer = self.0.lock().expect("a writer");
+        writer.seq += 1;
+        message["seq"] = json!(writer.seq);
+        let body = message.to_string();
+        let _ = write!(
+            writer.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = writer.output.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn should_read_framed_messages() {
+        let body = r#"{"seq":1,"type":"request","command":"threads"}"#;
+        let mut input = Cursor::new(format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+        let message = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(message["command"], "threads");
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+}
@@ -0,0 +1,10 @@
+pub mod backend;
+pub mod cli;
+pub mod config;
+pub mod consts;
+pub mod dap;
+pub mod helpers;
+pub mod lint;
+pub mod methods;
+pub mod spec;
+pub mod tests;
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::spec::{inference::Inference, parser::Parse, resolver::Resolution, syntax::TextRange};
+
+pub mod rules;
+
+#[derive(Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Warn,
+    Error,
+}
+
+/// Rule levels by rule name. Rules that are not listed keep their default.
+pub type LintConfig = HashMap<String, RuleLevel>;
+
+pub struct LintContext<'a> {
+    pub parse: &'a Parse,
+    pub resolution: &'a Resolution,
+    pub inference: &'a Inference,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct LintIssue {
+    pub range: TextRange,
+    pub message: String,
+    /// Marks code that can be removed, which editors usually fade out.
+    pub unnecessary: bool,
+    /// Other places in the file worth looking at, with a note for each.
+    pub related: Vec<(TextRange, String)>,
+}
+
+pub struct Rule {
+    pub name: &'static str,
+    pub default_level: RuleLevel,
+    pub check: fn(&LintContext) -> Vec<LintIssue>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct LintDiagnostic {
+    pub rule: &'static str,
+    pub level: RuleLevel,
+    pub issue: LintIssue,
+}
+
+pub fn rule_level(rule: &Rule, config: &LintConfig) -> RuleLevel {
+    config.get(rule.name).copied().unwrap_or(rule.default_level)
+}
+
+pub fn lint(parse: &Parse, config: &LintConfig) -> Vec<LintDiagnostic> {
+    let root = parse.syntax();
+    let resolution = Resolution::resolve(&root);
+    let inference = Inference::infer(&root, &resolution);
+    let context = LintContext {
+        parse,
+        resolution: &resolution,
+        inference: &inference,
+    };
+    let mut diagnostics: Vec<LintDiagnostic> = rules::RULES
+        .iter()
+        .filter_map(|rule| match rule_level(rule, config) {
+            RuleLevel::Off => None,
+            level => Some((rule, level)),
+        })
+        .flat_map(|(rule, level)| {
+            (rule.check)(&context)
+                .into_iter()
+                .map(move |issue| LintDiagnostic {
+                    rule: rule.name,
+                    level,
+                    issue,
+                })
+        })
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.issue.range.start);
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::parser::parse;
+
+    use super::*;
+
+    #[test]
+    fn should_apply_configured_levels() {
+        let parse = parse("severo a = 1\nsevero b = 2\nb = b");
+        let diagnostics = lint(&parse, &LintConfig::new());
+        let rules: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.level))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("unused-variable", RuleLevel::Warn),
+                ("self-assignment", RuleLevel::Warn)
+            ]
+        );
+
+        let config = LintConfig::from([
+            ("unused-variable".to_string(), RuleLevel::Off),
+            ("self-assignment".to_string(), RuleLevel::Error),
+        ]);
+        let rules: Vec<_> = lint(&parse, &config)
+            .iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.level))
+            .collect();
+        assert_eq!(rules, vec![("self-assignment", RuleLevel::Error)]);
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+
+use crate::spec::{
+    ast::{AssignExpr, AstNode, Block, CallExpr, Expr, IfStmt, Stmt, WhileStmt},
+    builtin_functions::get_builtin_functions,
+    helpers::edit_distance,
+    resolver::{Reference, Resolution, ScopeId, Symbol, SymbolId, SymbolKind},
+    syntax::{NodeKind, SyntaxNode, TextRange},
+};
+
+use super::{LintContext, LintIssue, Rule, RuleLevel};
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: "undefined-variable",
+        default_level: RuleLevel::Error,
+        check: undefined_variable,
+    },
+    Rule {
+        name: "unused-variable",
+        default_level: RuleLevel::Warn,
+        check: unused_variable,
+    },
+    Rule {
+        name: "shadowed-declaration",
+        default_level: RuleLevel::Warn,
+        check: shadowed_declaration,
+    },
+    Rule {
+        name: "use-before-declaration",
+        default_level: RuleLevel::Warn,
+        check: use_before_declaration,
+    },
+    Rule {
+        name: "unreachable-code",
+        default_level: RuleLevel::Warn,
+        check: unreachable_code,
+    },
+    Rule {
+        name: "empty-print",
+        default_level: RuleLevel::Warn,
+        check: empty_print,
+    },
+    Rule {
+        name: "self-assignment",
+        default_level: RuleLevel::Warn,
+        check: self_assignment,
+    },
+    Rule {
+        name: "constant-condition",
+        default_level: RuleLevel::Warn,
+        check: constant_condition,
+    },
+    Rule {
+        name: "type-mismatch",
+        default_level: RuleLevel::Error,
+        check: type_mismatch,
+    },
+];
+
+fn issue(range: TextRange, message: String) -> LintIssue {
+    LintIssue {
+        range,
+        message,
+        unnecessary: false,
+        related: Vec::new(),
+    }
+}
+
+fn nodes<N: AstNode>(context: &LintContext) -> impl Iterator<Item = N> {
+    context.parse.syntax().descendants().filter_map(N::cast)
+}
+
+/// Names that are not declared anywhere they could be seen from, with the
+/// closest visible name as a suggestion.
+fn undefined_variable(context: &LintContext) -> Vec<LintIssue> {
+    let resolution = context.resolution;
+    undefined_references(resolution)
+        .map(|reference| {
+            let message = format!("Undefined variable `{}`", reference.name);
+            LintIssue {
+                related: suggest_symbol(resolution, reference)
+                    .map(|symbol| (symbol.range, format!("did you mean `{}`?", symbol.name)))
+                    .into_iter()
+                    .collect(),
+                ..issue(reference.range, message)
+            }
+        })
+        .collect()
+}
+
+pub fn undefined_references(resolution: &Resolution) -> impl Iterator<Item = &Reference> {
+    let builtins = get_builtin_functions();
+    resolution
+        .references
+        .iter()
+        .filter(move |reference| reference.symbol.is_none() && !builtins.contains(&reference.name))
+}
+
+/// The visible symbol whose name is closest to the one of `reference`, if
+/// it is close enough to be a typo.
+pub fn suggest_symbol<'a>(resolution: &'a Resolution, reference: &Reference) -> Option<&'a Symbol> {
+    let max_distance = (reference.name.chars().count() / 3).max(1);
+    resolution
+        .visible_symbols(reference.range.start)
+        .into_iter()
+        .map(|symbol| &resolution.symbols[symbol])
+        .map(|symbol| (edit_distance(&reference.name, &symbol.name), symbol))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, symbol)| symbol)
+}
+
+/// Variables and functions that are never read.
+fn unused_variable(context: &LintContext) -> Vec<LintIssue> {
+    let resolution = context.resolution;
+    unused_symbols(resolution)
+        .map(|symbol| LintIssue {
+            unnecessary: true,
+            ..issue(symbol.range, format!("Unused variable `{}`", symbol.name))
+        })
+        .collect()
+}
+
+pub fn unused_symbols(resolution: &Resolution) -> impl Iterator<Item = &Symbol> {
+    resolution
+        .symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| matches!(symbol.kind, SymbolKind::Variable | SymbolKind::Function))
+        .filter(|(id, _)| {
+            !resolution
+                .references_to(*id)
+                .any(|reference| !reference.is_write)
+        })
+        .map(|(_, symbol)| symbol)
+}
+
+/// Declarations whose name is already declared in the same or an enclosing
+/// scope.
+fn shadowed_declaration(context: &LintContext) -> Vec<LintIssue> {
+    let resolution = context.resolution;
+    // The first declaration of every name in every scope.
+    let mut first_declarations: HashMap<(ScopeId, &str), SymbolId> = HashMap::new();
+    for (id, symbol) in resolution.symbols.iter().enumerate() {
+        first_declarations
+            .entry((symbol.scope, symbol.name.as_str()))
+            .or_insert(id);
+    }
+    resolution
+        .symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| symbol.kind != SymbolKind::Parameter)
+        .filter(|(id, symbol)| {
+            resolution.scope_chain(symbol.scope).any(|scope| {
+                first_declarations
+                    .get(&(scope, symbol.name.as_str()))
+                    .is_some_and(|first| first < id)
+            })
+        })
+        .map(|(_, symbol)| {
+            issue(
+                symbol.range,
+                format!("`{}` shadows an earlier declaration", symbol.name),
+            )
+        })
+        .collect()
+}
+
+fn use_before_declaration(context: &LintContext) -> Vec<LintIssue> {
+    context
+        .resolution
+        .references
+        .iter()
+        .filter(|reference| reference.before_declaration)
+        .map(|reference| {
+            issue(
+                reference.range,
+                format!("`{}` is used before its declaration", reference.name),
+            )
+        })
+        .collect()
+}
+
+/// Statements that follow a `return` in the same block.
+fn unreachable_code(context: &LintContext) -> Vec<LintIssue> {
+    let mut statement_lists: Vec<Vec<Stmt>> = nodes::<Block>(context)
+        .map(|block| block.statements().collect())
+        .collect();
+    statement_lists.push(context.parse.tree().statements().collect());
+
+    statement_lists
+        .into_iter()
+        .filter_map(|statements| {
+            let return_index = statements
+                .iter()
+                .position(|statement| matches!(statement, Stmt::ReturnStmt(_)))?;
+            let first = statements.get(return_index + 1)?;
+            let last = statements.last()?;
+            Some(LintIssue {
+                unnecessary: true,
+                ..issue(
+                    first.text_range().cover(last.text_range()),
+                    "Unreachable code".to_string(),
+                )
+            })
+        })
+        .collect()
+}
+
+fn empty_print(context: &LintContext) -> Vec<LintIssue> {
+    nodes::<CallExpr>(context)
+        .filter(|call| {
+            let is_print = matches!(
+                call.callee(),
+                Some(Expr::NameRef(name_ref)) if name_ref.text() == "print"
+            );
+            let no_args = call.arg_list().is_some_and(|arg_list| {
+                arg_list.args().next().is_none() && arg_list.r_paren().is_some()
+            });
+            is_print && no_args
+        })
+        .map(|call| {
+            issue(
+                call.text_range(),
+                "`print` is called without arguments".to_string(),
+            )
+        })
+        .collect()
+}
+
+fn self_assignment(context: &LintContext) -> Vec<LintIssue> {
+    nodes::<AssignExpr>(context)
+        .filter(|assign| match (assign.target(), assign.value()) {
+            (Some(target @ Expr::NameRef(_)), Some(value @ Expr::NameRef(_)))
+            | (Some(target @ Expr::FieldExpr(_)), Some(value @ Expr::FieldExpr(_))) => {
+                significant_text(target.syntax()) == significant_text(value.syntax())
+            }
+            _ => false,
+        })
+        .map(|assign| {
+            let target = assign
+                .target()
+                .map(|target| significant_text(target.syntax()));
+            issue(
+                assign.text_range(),
+                format!("`{}` is assigned to itself", target.unwrap_or_default()),
+            )
+        })
+        .collect()
+}
+
+/// Conditions of `if`, `elif` and `while` made only of literals. `while true`
+/// is left alone since it is the usual way to write an endless loop.
+fn constant_condition(context: &LintContext) -> Vec<LintIssue> {
+    let if_conditions = nodes::<IfStmt>(context).flat_map(|if_stmt| {
+        let elif_conditions: Vec<Expr> = if_stmt
+            .elif_branches()
+            .filter_map(|branch| branch.condition())
+            .collect();
+        if_stmt.condition().into_iter().chain(elif_conditions)
+    });
+    let while_conditions = nodes::<WhileStmt>(context)
+        .filter_map(|while_stmt| while_stmt.condition())
+        .filter(|condition| significant_text(condition.syntax()) != "true");
+
+    if_conditions
+        .chain(while_conditions)
+        .filter(|condition| is_constant(condition.syntax()))
+        .map(|condition| issue(condition.text_range(), "Condition is constant".to_string()))
+        .collect()
+}
+
+fn type_mismatch(context: &LintContext) -> Vec<LintIssue> {
+    context
+        .inference
+        .mismatches
+        .iter()
+        .map(|mismatch| issue(mismatch.range, mismatch.message.clone()))
+        .collect()
+}
+
+fn is_constant(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        NodeKind::Literal => true,
+        NodeKind::ParenExpr | NodeKind::PrefixExpr | NodeKind::BinaryExpr => {
+            node.children().all(|child| is_constant(&child))
+        }
+        _ => false,
+    }
+}
+
+fn significant_text(node: &SyntaxNode) -> String {
+    node.descendant_tokens()
+        .filter(|token| !token.kind().is_trivia())
+        .map(|token| token.text().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lint::{lint, LintConfig},
+        spec::parser::parse,
+    };
+
+    use super::*;
+
+    fn check(rule: &str, source: &str) -> Vec<(String, String)> {
+        let config = RULES
+            .iter()
+            .filter(|other| other.name != rule)
+            .map(|other| (other.name.to_string(), RuleLevel::Off))
+            .collect::<LintConfig>();
+        lint(&parse(source), &config)
+            .into_iter()
+            .map(|diagnostic| {
+                let range = diagnostic.issue.range;
+                (
+                    source[range.start..range.end].to_string(),
+                    diagnostic.issue.message,
+                )
+            })
+            .collect()
+    }
+
+    fn found(text: &str, message: &str) -> (String, String) {
+        (text.to_string(), message.to_string())
+    }
+
+    #[test]
+    fn should_report_undefined_variables() {
+        let source = "severo variableName = 1\nprint(variableNam)\nprint(other)";
+        assert_eq!(
+            check("undefined-variable", source),
+            vec![
+                found("variableNam", "Undefined variable `variableNam`"),
+                found("other", "Undefined variable `other`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_suggest_the_closest_visible_name() {
+        let source = "severo count = 1\n{ severo counter = 2 }\nsevero f = fun(amount) { print(amout + counte) }";
+        let config = RULES
+            .iter()
+            .filter(|rule| rule.name != "undefined-variable")
+            .map(|rule| (rule.name.to_string(), RuleLevel::Off))
+            .collect::<LintConfig>();
+        let related: Vec<Vec<(String, String)>> = lint(&parse(source), &config)
+            .into_iter()
+            .map(|diagnostic| {
+                diagnostic
+                    .issue
+                    .related
+                    .into_iter()
+                    .map(|(range, message)| (source[range.start..range.end].to_string(), message))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            related,
+            vec![
+                vec![found("amount", "did you mean `amount`?")],
+                vec![found("count", "did you mean `count`?")],
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_unused_variables() {
+        let source = "severo a = 1\nsevero b = 2\nb = 4\nsevero f = fun(x) {}\nprint(b)";
+        assert_eq!(
+            check("unused-variable", source),
+            vec![
+                found("a", "Unused variable `a`"),
+                found("f", "Unused variable `f`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_shadowed_declarations() {
+        let source =
+            "severo a = 1\nsevero a = 2\nsevero f = fun(x) { severo x = 1\n{ severo a = 3 } }";
+        assert_eq!(
+            check("shadowed-declaration", source),
+            vec![
+                found("a", "`a` shadows an earlier declaration"),
+                found("x", "`x` shadows an earlier declaration"),
+                found("a", "`a` shadows an earlier declaration"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_use_before_declaration() {
+        let source = "print(a)\nsevero a = 1\nsevero f = fun() { return a }";
+        assert_eq!(
+            check("use-before-declaration", source),
+            vec![found("a", "`a` is used before its declaration")]
+        );
+    }
+
+    #[test]
+    fn should_report_unreachable_code() {
+        let source = "severo f = fun() {\n  return 1\n  print(1)\n  print(2)\n}";
+        assert_eq!(
+            check("unreachable-code", source),
+            vec![found("print(1)\n  print(2)", "Unreachable code")]
+        );
+    }
+
+    #[test]
+    fn should_report_empty_print() {
+        let source = "print()\nprint(1)\nsevero f = fun() { print() }\nprint(";
+        assert_eq!(
+            check("empty-print", source),
+            vec![
+                found("print()", "`print` is called without arguments"),
+                found("print()", "`print` is called without arguments"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_self_assignment() {
+        let source = "severo a = 1\na = a\nthis.x = this . x\na = b";
+        assert_eq!(
+            check("self-assignment", source),
+            vec![
+                found("a = a", "`a` is assigned to itself"),
+                found("this.x = this . x", "`this.x` is assigned to itself"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_constant_conditions() {
+        let source = "if 1 > 2 {} elif a {} elif (false) {}\nwhile true {}\nwhile !nil {}";
+        assert_eq!(
+            check("constant-condition", source),
+            vec![
+                found("1 > 2", "Condition is constant"),
+                found("(false)", "Condition is constant"),
+                found("!nil", "Condition is constant"),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_type_mismatches() {
+        let source = "severo a = \"a\"\nprint(a - 1)\nprint(a + \"b\")";
+        assert_eq!(
+            check("type-mismatch", source),
+            vec![found("a", "Expected a number, found string")]
+        );
+    }
+}
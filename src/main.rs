@@ -1,17 +1,34 @@
-use backend::Backend;
-use tower_lsp::{LspService, Server};
+use std::{env, io, process};
 
-pub mod backend;
-pub mod consts;
-pub mod helpers;
-pub mod methods;
-pub mod spec;
-pub mod tests;
+use severo_lsp::{backend::Backend, cli};
+use tower_lsp::{LspService, Server};
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("fmt") => {
+            let code = cli::fmt::run(
+                &args[1..],
+                &mut io::stdin(),
+                &mut io::stdout(),
+                &mut io::stderr(),
+            );
+            process::exit(code);
+        }
+        Some("check") => {
+            let code = cli::check::run(&args[1..], &mut io::stdout(), &mut io::stderr());
+            process::exit(code);
+        }
+        Some("repl") => {
+            let code = cli::repl::run(&args[1..], &mut io::stdout(), &mut io::stderr());
+            process::exit(code);
+        }
+        _ => {}
+    }
+
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
-    let (service, socket) = LspService::new(|client| Backend { client });
+    let (service, socket) = LspService::new(Backend::new);
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -0,0 +1,367 @@
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams, Range,
+    SymbolKind as LspSymbolKind, Url,
+};
+use urlencoding::decode;
+
+use crate::spec::{
+    ast::{AstNode, CallExpr, Expr, Method},
+    line_index::LineIndex,
+    parser::parse,
+    resolver::{Resolution, SymbolId, SymbolKind},
+    syntax::{NodeKind, SyntaxNode, TextRange},
+};
+
+/// A call whose callee is a plain name.
+struct Call {
+    node: SyntaxNode,
+    /// The range of the callee's name.
+    name_range: TextRange,
+    name: String,
+    symbol: Option<SymbolId>,
+}
+
+/// A workspace file with what the call hierarchy needs to know about it.
+struct Analysis {
+    uri: Url,
+    line_index: LineIndex,
+    root: SyntaxNode,
+    resolution: Resolution,
+}
+
+impl Analysis {
+    fn new(uri: &Url, source: &str) -> Self {
+        let root = parse(source).syntax();
+        let resolution = Resolution::resolve(&root);
+        Analysis {
+            uri: uri.clone(),
+            line_index: LineIndex::new(source),
+            root,
+            resolution,
+        }
+    }
+
+    fn function_item(&self, symbol: SymbolId) -> CallHierarchyItem {
+        let symbol = &self.resolution.symbols[symbol];
+        CallHierarchyItem {
+            name: symbol.name.clone(),
+            kind: LspSymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: self.uri.clone(),
+            range: self.line_index.range(symbol.declaration_range),
+            selection_range: self.line_index.range(symbol.range),
+            data: None,
+        }
+    }
+
+    /// The function declared with `item`'s name range in this file.
+    fn function_of(&self, item: &CallHierarchyItem) -> Option<SymbolId> {
+        if item.uri != self.uri {
+            return None;
+        }
+        self.resolution.symbols.iter().position(|symbol| {
+            symbol.kind == SymbolKind::Function
+                && self.line_index.range(symbol.range) == item.selection_range
+        })
+    }
+
+    /// The top-level function called `name`, which other files can call.
+    fn global_function(&self, name: &str) -> Option<SymbolId> {
+        self.resolution.symbols.iter().position(|symbol| {
+            symbol.kind == SymbolKind::Function && symbol.scope == 0 && symbol.name == name
+        })
+    }
+
+    /// The function, method or file whose code runs the call at `node`.
+    fn caller_item(&self, node: &SyntaxNode) -> CallHierarchyItem {
+        for ancestor in node.ancestors().skip(1) {
+            match ancestor.kind() {
+                NodeKind::FunExpr => {
+                    let symbol = ancestor.parent().and_then(|declaration| {
+                        self.resolution.symbols.iter().position(|symbol| {
+                            symbol.kind == SymbolKind::Function
+                                && symbol.declaration_range == declaration.text_range()
+                        })
+                    });
+                    if let Some(symbol) = symbol {
+                        return self.function_item(symbol);
+                    }
+                }
+                NodeKind::Method => {
+                    let method = Method::cast(ancestor).expect("a method");
+                    let Some(name) = method.name() else {
+                        continue;
+                    };
+                    return CallHierarchyItem {
+                        name: name.text(),
+                        kind: LspSymbolKind::METHOD,
+                        tags: None,
+                        detail: None,
+                        uri: self.uri.clone(),
+                        range: self.line_index.range(method.text_range()),
+                        selection_range: self.line_index.range(name.text_range()),
+                        data: None,
+                    };
+                }
+                _ => {}
+            }
+        }
+        let name = self
+            .uri
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default();
+        let file_range = self.line_index.range(self.root.text_range());
+        CallHierarchyItem {
+            name: decode(name).map_or(name.to_string(), |name| name.into_owned()),
+            kind: LspSymbolKind::FILE,
+            tags: None,
+            detail: None,
+            uri: self.uri.clone(),
+            range: file_range,
+            selection_range: Range::new(file_range.start, file_range.start),
+            data: None,
+        }
+    }
+
+    fn calls(&self) -> Vec<Call> {
+        self.root
+            .descendants()
+            .filter_map(CallExpr::cast)
+            .filter_map(|call| {
+                let Some(Expr::NameRef(callee)) = call.callee() else {
+                    return None;
+                };
+                let reference = self
+                    .resolution
+                    .references
+                    .iter()
+                    .find(|reference| reference.range == callee.text_range())?;
+                Some(Call {
+                    node: call.syntax().clone(),
+                    name_range: reference.range,
+                    name: reference.name.clone(),
+                    symbol: reference.symbol,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Resolves a call to the function it runs, looking in the other files when
+/// the name is not declared in its own.
+fn callee_item(
+    analyses: &[Analysis],
+    file: &Analysis,
+    name: &str,
+    symbol: Option<SymbolId>,
+) -> Option<CallHierarchyItem> {
+    match symbol {
+        Some(symbol) => (file.resolution.symbols[symbol].kind == SymbolKind::Function)
+            .then(|| file.function_item(symbol)),
+        None => analyses
+            .iter()
+            .filter(|other| other.uri != file.uri)
+            .find_map(|other| Some(other.function_item(other.global_function(name)?))),
+    }
+}
+
+fn analyze(files: &[(Url, String)]) -> Vec<Analysis> {
+    files
+        .iter()
+        .map(|(uri, source)| Analysis::new(uri, source))
+        .collect()
+}
+
+fn push_range(
+    groups: &mut Vec<(CallHierarchyItem, Vec<Range>)>,
+    item: CallHierarchyItem,
+    range: Range,
+) {
+    match groups.iter_mut().find(|(other, _)| *other == item) {
+        Some((_, ranges)) => ranges.push(range),
+        None => groups.push((item, vec![range])),
+    }
+}
+
+pub fn prepare_call_hierarchy_method(
+    params: CallHierarchyPrepareParams,
+    files: Vec<(Url, String)>,
+) -> Result<Option<Vec<CallHierarchyItem>>, Error> {
+    let position = params.text_document_position_params;
+    let analyses = analyze(&files);
+    let Some(file) = analyses
+        .iter()
+        .find(|analysis| analysis.uri == position.text_document.uri)
+    else {
+        return Ok(None);
+    };
+    let Some(offset) = file.line_index.offset(position.position) else {
+        return Ok(None);
+    };
+    let item = match file.resolution.symbol_at(offset) {
+        Some(symbol) => (file.resolution.symbols[symbol].kind == SymbolKind::Function)
+            .then(|| file.function_item(symbol)),
+        None => file
+            .resolution
+            .references
+            .iter()
+            .find(|reference| reference.range.contains_inclusive(offset))
+            .and_then(|reference| callee_item(&analyses, file, &reference.name, None)),
+    };
+    Ok(item.map(|item| vec![item]))
+}
+
+pub fn incoming_calls_method(
+    params: CallHierarchyIncomingCallsParams,
+    files: Vec<(Url, String)>,
+) -> Result<Option<Vec<CallHierarchyIncomingCall>>, Error> {
+    let analyses = analyze(&files);
+    let mut groups = Vec::new();
+    for file in &analyses {
+        for call in file.calls() {
+            let callee = callee_item(&analyses, file, &call.name, call.symbol);
+            if callee.as_ref() == Some(&params.item) {
+                let range = file.line_index.range(call.name_range);
+                push_range(&mut groups, file.caller_item(&call.node), range);
+            }
+        }
+    }
+    let calls = groups
+        .into_iter()
+        .map(|(from, from_ranges)| CallHierarchyIncomingCall { from, from_ranges })
+        .collect();
+    Ok(Some(calls))
+}
+
+pub fn outgoing_calls_method(
+    params: CallHierarchyOutgoingCallsParams,
+    files: Vec<(Url, String)>,
+) -> Result<Option<Vec<CallHierarchyOutgoingCall>>, Error> {
+    let analyses = analyze(&files);
+    let Some(file) = analyses
+        .iter()
+        .find(|analysis| analysis.function_of(&params.item).is_some())
+    else {
+        return Ok(None);
+    };
+    let mut groups = Vec::new();
+    for call in file.calls() {
+        if file.caller_item(&call.node) != params.item {
+            continue;
+        }
+        if let Some(callee) = callee_item(&analyses, file, &call.name, call.symbol) {
+            let range = file.line_index.range(call.name_range);
+            push_range(&mut groups, callee, range);
+        }
+    }
+    let calls = groups
+        .into_iter()
+        .map(|(to, from_ranges)| CallHierarchyOutgoingCall { to, from_ranges })
+        .collect();
+    Ok(Some(calls))
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::{
+        PartialResultParams, Position, TextDocumentIdentifier, TextDocumentPositionParams,
+        WorkDoneProgressParams,
+    };
+
+    use super::*;
+
+    fn files() -> Vec<(Url, String)> {
+        let helpers = concat!(
+            "severo double = fun(n) {\n",
+            "    return n * 2\n",
+            "}\n",
+            "severo quadruple = fun(n) {\n",
+            "    return double(double(n))\n",
+            "}\n",
+        );
+        let main = concat!(
+            "class Report {\n",
+            "    total() { return double(2) }\n",
+            "}\n",
+            "print(quadruple(1))\n",
+        );
+        vec![
+            (
+                Url::parse("file:///w/helpers.severo").unwrap(),
+                helpers.to_string(),
+            ),
+            (
+                Url::parse("file:///w/main.severo").unwrap(),
+                main.to_string(),
+            ),
+        ]
+    }
+
+    fn prepare(file: usize, line: u32, character: u32) -> Option<CallHierarchyItem> {
+        let files = files();
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: files[file].0.clone(),
+                },
+                position: Position::new(line, character),
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        prepare_call_hierarchy_method(params, files)
+            .unwrap()
+            .map(|mut items| items.remove(0))
+    }
+
+    #[test]
+    fn should_prepare_from_declarations_and_calls_in_other_files() {
+        let item = prepare(0, 0, 8).unwrap();
+        assert_eq!(item.name, "double");
+        assert_eq!(
+            item.selection_range,
+            Range::new(Position::new(0, 7), Position::new(0, 13))
+        );
+        assert_eq!(prepare(1, 3, 8), prepare(0, 3, 8));
+        assert_eq!(prepare(1, 3, 8).unwrap().name, "quadruple");
+        assert_eq!(prepare(0, 1, 11), None);
+    }
+
+    #[test]
+    fn should_find_incoming_calls_across_files() {
+        let params = CallHierarchyIncomingCallsParams {
+            item: prepare(0, 0, 8).unwrap(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let calls: Vec<(String, usize)> = incoming_calls_method(params, files())
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|call| (call.from.name, call.from_ranges.len()))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![("quadruple".to_string(), 2), ("total".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn should_find_outgoing_calls() {
+        let params = CallHierarchyOutgoingCallsParams {
+            item: prepare(0, 3, 8).unwrap(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let calls: Vec<(String, usize)> = outgoing_calls_method(params, files())
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|call| (call.to.name, call.from_ranges.len()))
+            .collect();
+        assert_eq!(calls, vec![("double".to_string(), 2)]);
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
+    Diagnostic, TextEdit, Url, WorkspaceEdit,
+};
+
+use super::refactor::{enclosing_statement, get_refactors, line_indent};
+use crate::{
+    config::load_config_for,
+    lint::rules::{suggest_symbol, undefined_references, unused_symbols},
+    spec::{
+        line_index::LineIndex,
+        parser::parse,
+        resolver::Resolution,
+        scanner::scan_tokens,
+        syntax::{NodeKind, SyntaxNode, TextRange},
+    },
+};
+
+/// A fix for the problem found at `target`, as edits of the source.
+struct Fix {
+    title: String,
+    target: TextRange,
+    edits: Vec<(TextRange, String)>,
+    is_preferred: bool,
+}
+
+pub fn code_action_method(
+    params: CodeActionParams,
+    source: String,
+) -> Result<Option<CodeActionResponse>, Error> {
+    let line_index = LineIndex::new(&source);
+    let (Some(start), Some(end)) = (
+        line_index.offset(params.range.start),
+        line_index.offset(params.range.end),
+    ) else {
+        return Ok(None);
+    };
+    let range = TextRange::new(start, end);
+    let uri = params.text_document.uri;
+    let options = load_config_for(&uri).format_options(None);
+
+    let fixes = get_fixes(&source, &line_index)
+        .into_iter()
+        .filter(|fix| fix.target.start <= range.end && fix.target.end >= range.start)
+        .map(|fix| {
+            let target = line_index.range(fix.target);
+            let diagnostics: Vec<Diagnostic> = params
+                .context
+                .diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.range == target)
+                .cloned()
+                .collect();
+            CodeAction {
+                title: fix.title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(diagnostics),
+                edit: Some(workspace_edit(&uri, &line_index, fix.edits)),
+                is_preferred: Some(fix.is_preferred),
+                ..Default::default()
+            }
+        });
+    let refactors = get_refactors(&source, &line_index, range, &options)
+        .into_iter()
+        .map(|refactor| CodeAction {
+            title: refactor.title,
+            kind: Some(refactor.kind),
+            edit: Some(workspace_edit(&uri, &line_index, refactor.edits)),
+            ..Default::default()
+        });
+
+    let only = params.context.only.unwrap_or_default();
+    let actions: CodeActionResponse = fixes
+        .chain(refactors)
+        .filter(|action| {
+            let kind = action.kind.as_ref().map_or("", |kind| kind.as_str());
+            only.is_empty()
+                || only.iter().any(|only| {
+                    kind == only.as_str() || kind.starts_with(&format!("{}.", only.as_str()))
+                })
+        })
+        .map(CodeActionOrCommand::CodeAction)
+        .collect();
+    Ok(Some(actions))
+}
+
+pub fn workspace_edit(
+    uri: &Url,
+    line_index: &LineIndex,
+    edits: Vec<(TextRange, String)>,
+) -> WorkspaceEdit {
+    let edits = edits
+        .into_iter()
+        .map(|(range, new_text)| TextEdit {
+            range: line_index.range(range),
+            new_text,
+        })
+        .collect();
+    WorkspaceEdit {
+        changes: Some(HashMap::from([(uri.clone(), edits)])),
+        ..Default::default()
+    }
+}
+
+fn get_fixes(source: &str, line_index: &LineIndex) -> Vec<Fix> {
+    let parse = parse(source);
+    let root = parse.syntax();
+    let resolution = Resolution::resolve(&root);
+    let mut fixes = Vec::new();
+
+    for reference in undefined_references(&resolution) {
+        if let Some(symbol) = suggest_symbol(&resolution, reference) {
+            fixes.push(Fix {
+                title: format!("Change to `{}`", symbol.name),
+                target: reference.range,
+                edits: vec![(reference.range, symbol.name.clone())],
+                is_preferred: true,
+            });
+        }
+        let statement = root
+            .token_at_offset(reference.range.start)
+            .and_then(|token| enclosing_statement(&token.parent()));
+        if let Some(statement) = statement {
+            let statement_start = statement.text_range().start;
+            let indent = line_indent(source, line_index, statement_start);
+            fixes.push(Fix {
+                title: format!("Declare `{}` with `severo`", reference.name),
+                target: reference.range,
+                edits: vec![(
+                    TextRange::empty(statement_start),
+                    format!("severo {} = nil\n{}", reference.name, indent),
+                )],
+                is_preferred: false,
+            });
+        }
+    }
+
+    for error in scan_tokens(source.to_string()).errors {
+        if error.message == "Unterminated string" {
+            let line_end = line_index.line_end(error.span.start.line);
+            fixes.push(Fix {
+                title: "Close the string".to_string(),
+                target: TextRange::new(error.span.start.offset, error.span.end.offset),
+                edits: vec![(TextRange::empty(line_end), "\"".to_string())],
+                is_preferred: true,
+            });
+        }
+    }
+
+    for error in &parse.errors {
+        if error.message == "Expected `)`" && is_in_unclosed_call(&root, error.range.start) {
+            fixes.push(Fix {
+                title: "Add the missing `)`".to_string(),
+                target: error.range,
+                edits: vec![(error.range, ")".to_string())],
+                is_preferred: true,
+            });
+        }
+    }
+
+    for symbol in unused_symbols(&resolution) {
+        let Some(declaration) = root
+            .covering_element(symbol.declaration_range)
+            .into_node()
+            .filter(|node| node.kind() == NodeKind::VarDecl)
+        else {
+            continue;
+        };
+        // Removing a variable that is still assigned would leave the
+        // assignments without a declaration.
+        let assigned = resolution.references.iter().any(|reference| {
+            reference.is_write
+                && reference
+                    .symbol
+                    .is_some_and(|id| resolution.symbols[id].range == symbol.range)
+        });
+        if assigned || has_side_effects(&declaration) {
+            continue;
+        }
+        fixes.push(Fix {
+            title: format!("Remove unused `{}`", symbol.name),
+            target: symbol.range,
+            edits: vec![(
+                removal_range(source, line_index, declaration.text_range()),
+                String::new(),
+            )],
+            is_preferred: true,
+        });
+    }
+
+    fixes
+}
+
+fn is_in_unclosed_call(root: &SyntaxNode, offset: usize) -> bool {
+    let Some(token) = root.token_at_offset(offset.saturating_sub(1)) else {
+        return false;
+    };
+    token
+        .parent()
+        .ancestors()
+        .find(|node| node.kind() == NodeKind::ArgList)
+        .is_some_and(|arg_list| arg_list.text_range().end <= offset)
+}
+
+pub fn has_side_effects(node: &SyntaxNode) -> bool {
+    node.descendants()
+        .any(|node| matches!(node.kind(), NodeKind::CallExpr | NodeKind::AssignExpr))
+}
+
+/// Extends `range` to whole lines when nothing else shares them.
+pub fn removal_range(source: &str, line_index: &LineIndex, range: TextRange) -> TextRange {
+    let start_line = line_index.location(range.start).line;
+    let end_line = line_index.location(range.end).line;
+    let line_start = line_index.line_start(start_line);
+    let line_end = line_index.line_end(end_line);
+    let alone = source[line_start..range.start].trim().is_empty()
+        && source[range.end..line_end].trim().is_empty();
+    match (alone, end_line + 1 < line_index.line_count()) {
+        (true, true) => TextRange::new(line_start, line_index.line_start(end_line + 1)),
+        (true, false) => TextRange::new(line_start, line_end),
+        (false, _) => range,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::{
+        CodeActionContext, Position, Range, TextDocumentIdentifier, WorkDoneProgressParams,
+    };
+
+    use crate::spec::formatter::{apply_edits, Edit};
+
+    use super::*;
+
+    fn actions(source: &str, line: u32, character: u32) -> Vec<(String, String)> {
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::parse("file:///tmp/actions.severo").unwrap(),
+            },
+            range: Range::new(
+                Position::new(line, character),
+                Position::new(line, character),
+            ),
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: Default::default(),
+        };
+        let line_index = LineIndex::new(source);
+        code_action_method(params, source.to_string())
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|action| {
+                let CodeActionOrCommand::CodeAction(action) = action else {
+                    panic!("Expected a code action");
+                };
+                let edits: Vec<Edit> = action.edit.unwrap().changes.unwrap()
+                    [&Url::parse("file:///tmp/actions.severo").unwrap()]
+                    .iter()
+                    .map(|edit| Edit {
+                        range: TextRange::new(
+                            line_index.offset(edit.range.start).unwrap(),
+                            line_index.offset(edit.range.end).unwrap(),
+                        ),
+                        new_text: edit.new_text.clone(),
+                    })
+                    .collect();
+                (action.title, apply_edits(source, &edits))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_fix_misspelled_and_undeclared_names() {
+        let source = "severo variableName = 1\n{\n    print(variableNam)\n}";
+        assert_eq!(
+            actions(source, 2, 10),
+            vec![
+                (
+                    "Change to `variableName`".to_string(),
+                    "severo variableName = 1\n{\n    print(variableName)\n}".to_string()
+                ),
+                (
+                    "Declare `variableNam` with `severo`".to_string(),
+                    "severo variableName = 1\n{\n    severo variableNam = nil\n    print(variableNam)\n}"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_close_unterminated_strings() {
+        let source = "print(\"Hello)\nprint(1)";
+        assert_eq!(
+            actions(source, 0, 8),
+            vec![(
+                "Close the string".to_string(),
+                "print(\"Hello)\"\nprint(1)".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_add_missing_paren_to_calls() {
+        let source = "print(1 + 2\nprint(3)";
+        assert_eq!(
+            actions(source, 0, 11),
+            vec![(
+                "Add the missing `)`".to_string(),
+                "print(1 + 2)\nprint(3)".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_remove_unused_declarations() {
+        let source = "severo a = 1\nsevero b = 2\nprint(b)";
+        assert_eq!(
+            actions(source, 0, 7),
+            vec![(
+                "Remove unused `a`".to_string(),
+                "severo b = 2\nprint(b)".to_string()
+            )]
+        );
+        assert!(actions("severo a = print(1)", 0, 7).is_empty());
+    }
+
+    #[test]
+    fn should_keep_unused_declarations_that_are_assigned() {
+        assert!(actions("severo b = 2\nb = 4\n", 0, 7).is_empty());
+    }
+}
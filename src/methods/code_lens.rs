@@ -0,0 +1,101 @@
+use serde_json::json;
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{CodeLens, CodeLensParams, Command, Location, Position, Range};
+
+use crate::consts::{RUN_FILE_COMMAND, SHOW_REFERENCES_COMMAND};
+use crate::spec::{
+    line_index::LineIndex,
+    parser::parse,
+    resolver::{Resolution, SymbolKind},
+};
+
+pub fn code_lens_method(
+    params: CodeLensParams,
+    source: String,
+) -> Result<Option<Vec<CodeLens>>, Error> {
+    let uri = params.text_document.uri;
+    let line_index = LineIndex::new(&source);
+    let resolution = Resolution::resolve(&parse(&source).syntax());
+
+    let run = CodeLens {
+        range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+        command: Some(Command {
+            title: "Run file".to_string(),
+            command: RUN_FILE_COMMAND.to_string(),
+            arguments: Some(vec![json!(uri)]),
+        }),
+        data: None,
+    };
+    let references = resolution
+        .symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| matches!(symbol.kind, SymbolKind::Function | SymbolKind::Class))
+        .map(|(id, symbol)| {
+            let locations: Vec<Location> = resolution
+                .references_to(id)
+                .map(|reference| Location::new(uri.clone(), line_index.range(reference.range)))
+                .collect();
+            let title = match locations.len() {
+                1 => "1 reference".to_string(),
+                count => format!("{} references", count),
+            };
+            let range = line_index.range(symbol.range);
+            CodeLens {
+                range,
+                command: Some(Command {
+                    title,
+                    command: SHOW_REFERENCES_COMMAND.to_string(),
+                    arguments: Some(vec![json!(uri), json!(range.start), json!(locations)]),
+                }),
+                data: None,
+            }
+        });
+    Ok(Some(std::iter::once(run).chain(references).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::{
+        PartialResultParams, TextDocumentIdentifier, Url, WorkDoneProgressParams,
+    };
+
+    use super::*;
+
+    #[test]
+    fn should_count_references_of_functions_and_classes() {
+        let source = "severo f = fun() {}\nclass A {}\nf()\nf()\nA()\nsevero x = 1\nprint(x)";
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::parse("file:///tmp/lens.severo").unwrap(),
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let lenses: Vec<(u32, String, String)> = code_lens_method(params, source.to_string())
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|lens| {
+                let command = lens.command.unwrap();
+                (lens.range.start.line, command.title, command.command)
+            })
+            .collect();
+        assert_eq!(
+            lenses,
+            vec![
+                (0, "Run file".to_string(), RUN_FILE_COMMAND.to_string()),
+                (
+                    0,
+                    "2 references".to_string(),
+                    SHOW_REFERENCES_COMMAND.to_string()
+                ),
+                (
+                    1,
+                    "1 reference".to_string(),
+                    SHOW_REFERENCES_COMMAND.to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -72,7 +72,7 @@ pub fn completion_method(params: CompletionParams) -> Result<Option<CompletionRe
     }
 }
 
-fn get_completion_items(
+pub fn get_completion_items(
     word_or_part_of_it: String,
     variables: Vec<String>,
 ) -> Option<Vec<CompletionItem>> {
@@ -0,0 +1,198 @@
+use serde_json::Value;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, Location,
+    NumberOrString, Range, Url,
+};
+
+use crate::{
+    consts::SERVER_NAME,
+    lint::{lint, LintConfig, LintDiagnostic, RuleLevel},
+    spec::{line_index::LineIndex, parser::parse, scanner::scan_tokens},
+};
+
+pub fn get_diagnostics(uri: &Url, source: String, lint_config: &LintConfig) -> Vec<Diagnostic> {
+    let line_index = LineIndex::new(source.as_str());
+    let parse = parse(source.as_str());
+    let lint_diagnostics: Vec<Diagnostic> = lint(&parse, lint_config)
+        .into_iter()
+        .map(|diagnostic| lint_diagnostic(uri, &line_index, diagnostic))
+        .collect();
+    let parse_errors = parse.errors;
+    let scan_result = scan_tokens(source);
+    let scan_diagnostics = scan_result
+        .errors
+        .into_iter()
+        .map(|error| error_diagnostic(error.span.into(), error.message));
+    let parse_diagnostics = parse_errors
+        .into_iter()
+        .map(|error| error_diagnostic(line_index.range(error.range), error.message));
+    let mut diagnostics: Vec<Diagnostic> = scan_diagnostics
+        .chain(parse_diagnostics)
+        .chain(lint_diagnostics)
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+    diagnostics
+}
+
+/// Reads the rule levels from the `severo.lint` (or `lint`) section of the
+/// editor settings, ignoring unknown levels.
+pub fn get_lint_settings(settings: &Value) -> LintConfig {
+    let lint = settings
+        .pointer("/severo/lint")
+        .or_else(|| settings.get("lint"));
+    let Some(Value::Object(rules)) = lint else {
+        return LintConfig::new();
+    };
+    rules
+        .iter()
+        .filter_map(|(rule, level)| {
+            let level = serde_json::from_value::<RuleLevel>(level.clone()).ok()?;
+            Some((rule.clone(), level))
+        })
+        .collect()
+}
+
+fn lint_diagnostic(uri: &Url, line_index: &LineIndex, diagnostic: LintDiagnostic) -> Diagnostic {
+    let severity = match diagnostic.level {
+        RuleLevel::Error => DiagnosticSeverity::ERROR,
+        _ => DiagnosticSeverity::WARNING,
+    };
+    let tags = match diagnostic.issue.unnecessary {
+        true => Some(vec![DiagnosticTag::UNNECESSARY]),
+        false => None,
+    };
+    let related_information: Vec<DiagnosticRelatedInformation> = diagnostic
+        .issue
+        .related
+        .into_iter()
+        .map(|(range, message)| DiagnosticRelatedInformation {
+            location: Location {
+                uri: uri.clone(),
+                range: line_index.range(range),
+            },
+            message,
+        })
+        .collect();
+    Diagnostic {
+        range: line_index.range(diagnostic.issue.range),
+        severity: Some(severity),
+        code: Some(NumberOrString::String(diagnostic.rule.to_string())),
+        source: Some(SERVER_NAME.to_string()),
+        message: diagnostic.issue.message,
+        tags,
+        related_information: match related_information.is_empty() {
+            true => None,
+            false => Some(related_information),
+        },
+        ..Default::default()
+    }
+}
+
+pub fn error_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(SERVER_NAME.to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::Position;
+
+    use super::*;
+
+    fn uri() -> Url {
+        Url::parse("file:///tmp/diagnostics.severo").unwrap()
+    }
+
+    #[test]
+    fn should_return_no_diagnostics_for_valid_source() {
+        let source = "severo hello = \"Hello\\tWorld\"\nprint(hello)".to_string();
+        assert!(get_diagnostics(&uri(), source, &LintConfig::new()).is_empty());
+    }
+
+    #[test]
+    fn should_return_a_diagnostic_for_a_syntax_error() {
+        let source = "severo total = \nprint(total".to_string();
+        let diagnostics: Vec<Diagnostic> = get_diagnostics(&uri(), source, &LintConfig::new())
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR))
+            .collect();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Expected `)`");
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 11));
+    }
+
+    #[test]
+    fn should_point_undefined_variables_to_similar_names() {
+        let source = "severo variableName = 1\nprint(variableNam)".to_string();
+        let config = LintConfig::from([("unused-variable".to_string(), RuleLevel::Off)]);
+        let diagnostics = get_diagnostics(&uri(), source, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Undefined variable `variableNam`");
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            diagnostics[0].related_information,
+            Some(vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: uri(),
+                    range: Range::new(Position::new(0, 7), Position::new(0, 19)),
+                },
+                message: "did you mean `variableName`?".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn should_read_lint_settings() {
+        let settings = serde_json::json!({
+            "severo": {"lint": {"unused-variable": "error", "empty-print": "loud"}}
+        });
+        assert_eq!(
+            get_lint_settings(&settings),
+            LintConfig::from([("unused-variable".to_string(), RuleLevel::Error)])
+        );
+        assert!(get_lint_settings(&serde_json::json!(null)).is_empty());
+    }
+
+    #[test]
+    fn should_return_lint_warnings_with_their_rule() {
+        let source = "severo unused = 1".to_string();
+        let diagnostics = get_diagnostics(&uri(), source.clone(), &LintConfig::new());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("unused-variable".to_string()))
+        );
+        assert_eq!(diagnostics[0].tags, Some(vec![DiagnosticTag::UNNECESSARY]));
+
+        let config = LintConfig::from([("unused-variable".to_string(), RuleLevel::Off)]);
+        assert!(get_diagnostics(&uri(), source, &config).is_empty());
+    }
+
+    #[test]
+    fn should_return_a_diagnostic_for_an_invalid_escape() {
+        let source = "\nprint(\"bad \\x\")".to_string();
+        let diagnostics = get_diagnostics(&uri(), source, &LintConfig::new());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unknown escape sequence `\\x`");
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            diagnostics[0].range,
+            Range {
+                start: Position {
+                    line: 1,
+                    character: 11
+                },
+                end: Position {
+                    line: 1,
+                    character: 13
+                },
+            }
+        );
+    }
+}
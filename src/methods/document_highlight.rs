@@ -0,0 +1,103 @@
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{DocumentHighlight, DocumentHighlightKind, DocumentHighlightParams};
+
+use crate::spec::{line_index::LineIndex, parser::parse, resolver::Resolution, syntax::TextRange};
+
+pub fn document_highlight_method(
+    params: DocumentHighlightParams,
+    source: String,
+) -> Result<Option<Vec<DocumentHighlight>>, Error> {
+    let line_index = LineIndex::new(&source);
+    let position = params.text_document_position_params.position;
+    let Some(offset) = line_index.offset(position) else {
+        return Ok(None);
+    };
+    let highlights = get_highlights(&source, offset)
+        .into_iter()
+        .map(|(range, is_write)| DocumentHighlight {
+            range: line_index.range(range),
+            kind: Some(match is_write {
+                true => DocumentHighlightKind::WRITE,
+                false => DocumentHighlightKind::READ,
+            }),
+        })
+        .collect::<Vec<_>>();
+    match highlights.is_empty() {
+        true => Ok(None),
+        false => Ok(Some(highlights)),
+    }
+}
+
+/// The occurrences of the name at `offset`, with whether each one writes it.
+/// Names that do not resolve to a declaration match by text.
+fn get_highlights(source: &str, offset: usize) -> Vec<(TextRange, bool)> {
+    let resolution = Resolution::resolve(&parse(source).syntax());
+    let mut highlights: Vec<(TextRange, bool)> = match resolution.symbol_at(offset) {
+        Some(symbol) => std::iter::once((resolution.symbols[symbol].range, true))
+            .chain(
+                resolution
+                    .references_to(symbol)
+                    .map(|reference| (reference.range, reference.is_write)),
+            )
+            .collect(),
+        None => {
+            let Some(unresolved) = resolution
+                .references
+                .iter()
+                .find(|reference| reference.range.contains_inclusive(offset))
+            else {
+                return Vec::new();
+            };
+            resolution
+                .references
+                .iter()
+                .filter(|reference| reference.symbol.is_none() && reference.name == unresolved.name)
+                .map(|reference| (reference.range, reference.is_write))
+                .collect()
+        }
+    };
+    highlights.sort_by_key(|(range, _)| range.start);
+    highlights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlights(source: &str, offset: usize) -> Vec<(&str, bool)> {
+        get_highlights(source, offset)
+            .into_iter()
+            .map(|(range, is_write)| (&source[range.start..range.end], is_write))
+            .collect()
+    }
+
+    #[test]
+    fn should_highlight_declaration_writes_and_reads() {
+        let source =
+            "severo count = 1\ncount = count + 1\n{\n    severo count = 2\n}\nprint(count)";
+        let expected = vec![
+            ("count", true),
+            ("count", true),
+            ("count", false),
+            ("count", false),
+        ];
+        assert_eq!(highlights(source, 8), expected);
+        assert_eq!(highlights(source, source.len() - 2), expected);
+        let inner = source.find("    severo count").unwrap() + 12;
+        assert_eq!(highlights(source, inner), vec![("count", true)]);
+    }
+
+    #[test]
+    fn should_highlight_unresolved_names_by_text() {
+        let source = "print(missing)\nmissing = 1\nprint(other)";
+        assert_eq!(
+            highlights(source, 8),
+            vec![("missing", false), ("missing", true)]
+        );
+        assert_eq!(
+            highlights(source, 0),
+            vec![("print", false), ("print", false)]
+        );
+        assert!(highlights("severo a = 1", 11).is_empty());
+    }
+}
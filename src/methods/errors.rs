@@ -1 +1,3 @@
 pub const NO_FILE_OR_DIRECTORY: &str = "No such a file or directory";
+pub const UNKNOWN_COMMAND: &str = "Unknown command";
+pub const MISSING_FILE_ARGUMENT: &str = "Expected the file to run";
@@ -0,0 +1,151 @@
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind, FoldingRangeParams};
+
+use crate::spec::{
+    ast::{AstNode, Block},
+    line_index::LineIndex,
+    parser::parse,
+    syntax::NodeKind,
+    types::TokenType,
+};
+
+pub fn folding_range_method(
+    _params: FoldingRangeParams,
+    source: String,
+) -> Result<Option<Vec<FoldingRange>>, Error> {
+    Ok(Some(get_folding_ranges(&source)))
+}
+
+pub fn get_folding_ranges(source: &str) -> Vec<FoldingRange> {
+    let line_index = LineIndex::new(source);
+    let line_of = |offset: usize| line_index.location(offset).line as u32;
+    let root = parse(source).syntax();
+    let mut ranges = Vec::new();
+
+    // Braces fold up to the line before the closing one, which stays visible.
+    for node in root.descendants() {
+        let (l_brace, r_brace) = match node.kind() {
+            NodeKind::Block => {
+                let block = Block::cast(node).expect("a block");
+                (block.l_brace(), block.r_brace())
+            }
+            NodeKind::ClassDecl => (
+                node.first_token_of(TokenType::LeftBrace),
+                node.first_token_of(TokenType::RightBrace),
+            ),
+            _ => continue,
+        };
+        let (Some(l_brace), Some(r_brace)) = (l_brace, r_brace) else {
+            continue;
+        };
+        let start_line = line_of(l_brace.text_range().start);
+        let end_line = line_of(r_brace.text_range().start).saturating_sub(1);
+        if end_line > start_line {
+            ranges.push(fold(start_line, end_line, None));
+        }
+    }
+
+    let mut comment_run: Option<(u32, u32)> = None;
+    for token in root.descendant_tokens() {
+        let start_line = line_of(token.text_range().start);
+        match token.kind() {
+            TokenType::BlockComment => {
+                let end_line = line_of(token.text_range().end);
+                if end_line > start_line {
+                    ranges.push(fold(start_line, end_line, Some(FoldingRangeKind::Comment)));
+                }
+            }
+            TokenType::LineComment | TokenType::DocComment => {
+                let line_start = line_index.line_start(start_line as usize);
+                let own_line = source[line_start..token.text_range().start]
+                    .trim()
+                    .is_empty();
+                comment_run = match comment_run {
+                    Some((first, last)) if own_line && last + 1 == start_line => {
+                        Some((first, start_line))
+                    }
+                    run => {
+                        push_comment_run(&mut ranges, run);
+                        own_line.then_some((start_line, start_line))
+                    }
+                };
+            }
+            kind if kind.is_trivia() => {}
+            _ => push_comment_run(&mut ranges, comment_run.take()),
+        }
+    }
+    push_comment_run(&mut ranges, comment_run);
+
+    ranges.sort_by_key(|range| (range.start_line, range.end_line));
+    ranges
+}
+
+fn push_comment_run(ranges: &mut Vec<FoldingRange>, run: Option<(u32, u32)>) {
+    if let Some((first, last)) = run.filter(|(first, last)| last > first) {
+        ranges.push(fold(first, last, Some(FoldingRangeKind::Comment)));
+    }
+}
+
+fn fold(start_line: u32, end_line: u32, kind: Option<FoldingRangeKind>) -> FoldingRange {
+    FoldingRange {
+        start_line,
+        end_line,
+        kind,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folds(source: &str) -> Vec<(u32, u32, bool)> {
+        get_folding_ranges(source)
+            .into_iter()
+            .map(|range| {
+                let is_comment = range.kind == Some(FoldingRangeKind::Comment);
+                (range.start_line, range.end_line, is_comment)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_fold_blocks_and_classes() {
+        let source = concat!(
+            "class A {\n",
+            "    m() {\n",
+            "        print(1)\n",
+            "    }\n",
+            "}\n",
+            "if a {\n",
+            "    print(1)\n",
+            "} else {\n",
+            "    print(2)\n",
+            "}\n",
+            "while a { print(1) }\n",
+        );
+        assert_eq!(
+            folds(source),
+            vec![(0, 3, false), (1, 2, false), (5, 6, false), (7, 8, false)]
+        );
+    }
+
+    #[test]
+    fn should_fold_comments() {
+        let source = concat!(
+            "// one\n",
+            "// two\n",
+            "print(1) // trailing\n",
+            "// alone\n",
+            "/* a\n",
+            "   b */\n",
+            "/// doc\n",
+            "/// more\n",
+            "severo f = fun() {}\n",
+        );
+        assert_eq!(
+            folds(source),
+            vec![(0, 1, true), (4, 5, true), (6, 7, true)]
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{
+    DocumentFormattingParams, DocumentOnTypeFormattingParams, DocumentRangeFormattingParams,
+    FormattingOptions, Range, TextEdit, Url,
+};
+
+use crate::{
+    config::load_config_for,
+    spec::{
+        formatter::{format_edits, indent_depth, matching_left_brace, Edit},
+        line_index::LineIndex,
+        syntax::TextRange,
+    },
+};
+
+pub fn formatting_method(
+    params: DocumentFormattingParams,
+    source: String,
+) -> Result<Option<Vec<TextEdit>>, Error> {
+    let edits = get_format_edits(&params.text_document.uri, &params.options, &source);
+    Ok(edits.map(|edits| to_text_edits(&source, edits)))
+}
+
+pub fn range_formatting_method(
+    params: DocumentRangeFormattingParams,
+    source: String,
+) -> Result<Option<Vec<TextEdit>>, Error> {
+    let line_index = LineIndex::new(&source);
+    let (Some(start), Some(end)) = (
+        line_index.offset(params.range.start),
+        line_index.offset(params.range.end),
+    ) else {
+        return Ok(None);
+    };
+    let range = TextRange::new(start, end);
+    let edits = get_format_edits(&params.text_document.uri, &params.options, &source);
+    Ok(edits.map(|edits| {
+        let edits = edits
+            .into_iter()
+            .filter(|edit| edit.range.start <= range.end && edit.range.end >= range.start)
+            .collect();
+        to_text_edits(&source, edits)
+    }))
+}
+
+/// Closing a block re-formats it, and a new line gets the indentation of the
+/// block it is in. Both fall back to re-indenting the current line when the
+/// file does not parse yet.
+pub fn on_type_formatting_method(
+    params: DocumentOnTypeFormattingParams,
+    source: String,
+) -> Result<Option<Vec<TextEdit>>, Error> {
+    let position = params.text_document_position.position;
+    let uri = &params.text_document_position.text_document.uri;
+    let line_index = LineIndex::new(&source);
+    let Some(offset) = line_index.offset(position) else {
+        return Ok(None);
+    };
+
+    if params.ch == "}" {
+        let Some(left_brace) = matching_left_brace(&source, offset) else {
+            return Ok(None);
+        };
+        if let Some(edits) = get_format_edits(uri, &params.options, &source) {
+            let block = TextRange::new(left_brace, offset);
+            let edits = edits
+                .into_iter()
+                .filter(|edit| block.contains(edit.range.start) && edit.range.end <= block.end)
+                .collect();
+            return Ok(Some(to_text_edits(&source, edits)));
+        }
+    }
+
+    let options = load_config_for(uri).format_options(Some(&params.options));
+    let line = position.line as usize;
+    let line_start = line_index.line_start(line);
+    let line_text = line_index.line_text(line);
+    let indent_end = line_start + line_text.len() - line_text.trim_start().len();
+    let Some(depth) = indent_depth(&source, indent_end) else {
+        return Ok(None);
+    };
+    let indent = options.indent(depth);
+    if source[line_start..indent_end] == indent {
+        return Ok(Some(vec![]));
+    }
+    Ok(Some(vec![TextEdit {
+        range: Range::new(
+            line_index.position(line_start),
+            line_index.position(indent_end),
+        ),
+        new_text: indent,
+    }]))
+}
+
+pub fn get_format_edits(uri: &Url, options: &FormattingOptions, source: &str) -> Option<Vec<Edit>> {
+    let options = load_config_for(uri).format_options(Some(options));
+    format_edits(source, &options).ok()
+}
+
+pub fn to_text_edits(source: &str, edits: Vec<Edit>) -> Vec<TextEdit> {
+    let line_index = LineIndex::new(source);
+    edits
+        .into_iter()
+        .map(|edit| TextEdit {
+            range: line_index.range(edit.range),
+            new_text: edit.new_text,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::{
+        Position, TextDocumentIdentifier, TextDocumentPositionParams, WorkDoneProgressParams,
+    };
+
+    use super::*;
+
+    fn options() -> FormattingOptions {
+        FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        }
+    }
+
+    fn document() -> TextDocumentIdentifier {
+        TextDocumentIdentifier {
+            uri: Url::parse("untitled:format.severo").unwrap(),
+        }
+    }
+
+    #[test]
+    fn should_format_the_whole_document() {
+        let params = DocumentFormattingParams {
+            text_document: document(),
+            options: options(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let edits = formatting_method(params, "severo a=1\n".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit {
+                    range: Range::new(Position::new(0, 8), Position::new(0, 8)),
+                    new_text: " ".to_string(),
+                },
+                TextEdit {
+                    range: Range::new(Position::new(0, 9), Position::new(0, 9)),
+                    new_text: " ".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_only_format_the_requested_range() {
+        let params = DocumentRangeFormattingParams {
+            text_document: document(),
+            range: Range::new(Position::new(1, 0), Position::new(1, 10)),
+            options: options(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let edits = range_formatting_method(params, "severo a=1\nsevero b=2\n".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(edits.len(), 2);
+        assert!(edits.iter().all(|edit| edit.range.start.line == 1));
+    }
+
+    #[test]
+    fn should_not_format_invalid_documents() {
+        let params = DocumentFormattingParams {
+            text_document: document(),
+            options: options(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        assert_eq!(
+            formatting_method(params, "print(".to_string()).unwrap(),
+            None
+        );
+    }
+
+    fn on_type(source: &str, line: u32, character: u32, ch: &str) -> Vec<TextEdit> {
+        let params = DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: document(),
+                position: Position::new(line, character),
+            },
+            ch: ch.to_string(),
+            options: options(),
+        };
+        on_type_formatting_method(params, source.to_string())
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn should_indent_new_line_inside_block() {
+        let edits = on_type("severo f = fun(a) {\n", 1, 0, "\n");
+        assert_eq!(
+            edits,
+            vec![TextEdit {
+                range: Range::new(Position::new(1, 0), Position::new(1, 0)),
+                new_text: "    ".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_dedent_closing_brace_on_new_line() {
+        let edits = on_type("while a {\n  print(a)\n      }", 2, 0, "\n");
+        assert_eq!(
+            edits,
+            vec![TextEdit {
+                range: Range::new(Position::new(2, 0), Position::new(2, 6)),
+                new_text: String::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_format_block_when_closing_it() {
+        let source = "print(1)\nwhile a {\nprint( a )\n  }";
+        let edits = on_type(source, 3, 3, "}");
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit {
+                    range: Range::new(Position::new(1, 9), Position::new(2, 0)),
+                    new_text: "\n    ".to_string(),
+                },
+                TextEdit {
+                    range: Range::new(Position::new(2, 6), Position::new(2, 7)),
+                    new_text: String::new(),
+                },
+                TextEdit {
+                    range: Range::new(Position::new(2, 8), Position::new(2, 9)),
+                    new_text: String::new(),
+                },
+                TextEdit {
+                    range: Range::new(Position::new(2, 10), Position::new(3, 2)),
+                    new_text: "\n".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_reindent_closing_brace_of_invalid_code() {
+        let edits = on_type("while a {\n  print(\n      }", 2, 7, "}");
+        assert_eq!(
+            edits,
+            vec![TextEdit {
+                range: Range::new(Position::new(2, 0), Position::new(2, 6)),
+                new_text: String::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_ignore_braces_inside_strings() {
+        let params = DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: document(),
+                position: Position::new(0, 8),
+            },
+            ch: "}".to_string(),
+            options: options(),
+        };
+        let result = on_type_formatting_method(params, "print(\"}\")".to_string()).unwrap();
+        assert_eq!(result, None);
+    }
+}
@@ -1,75 +1,162 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-use tower_lsp::jsonrpc::{Error, ErrorCode};
+use tower_lsp::jsonrpc::Error;
 use tower_lsp::lsp_types::{
     Hover, HoverContents, HoverParams, MarkupContent, MarkupKind, Position, Range,
 };
-use urlencoding::decode;
 
 use crate::helpers::get_word_in_line_col_position::get_word_in_line_col_position;
-use crate::methods::errors::NO_FILE_OR_DIRECTORY;
+use crate::spec::{
+    ast::{AstNode, Expr, Name, VarDecl},
+    constant::ConstantFolder,
+    interpreter::Value,
+    line_index::LineIndex,
+    parser::{get_docs, parse},
+    resolver::Resolution,
+    scanner::scan_tokens,
+    syntax::NodeKind,
+};
 
-use super::texts::get_hover_text;
+use super::texts::{get_doc_hover_text, get_hover_text, get_value_hover_text};
 
-pub fn hover_method(params: HoverParams) -> Result<Option<Hover>, Error> {
-    let file_path = params
-        .text_document_position_params
-        .text_document
-        .uri
-        .path();
+pub fn hover_method(params: HoverParams, source: String) -> Result<Option<Hover>, Error> {
     let position = params.text_document_position_params.position;
+    let line_index = LineIndex::new(&source);
+    let line = position.line as usize;
+    let (word, start_col, end_col) = match line < line_index.line_count() {
+        true => get_word_in_line_col_position(
+            line_index.line_text(line).to_string(),
+            position.character as usize,
+        ),
+        false => (String::new(), 0, 0),
+    };
 
-    let decoded_path = decode(file_path).expect("UTF-8");
-    match File::open(decoded_path.to_string()) {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            let mut word = String::new();
-            let mut word_line: u32 = 0;
-            let mut word_range: (u32, u32) = (0, 0);
-
-            for (line_index, line) in reader.lines().enumerate() {
-                let line_position = position.line as usize;
-                if line_index < line_position {
-                    continue;
-                }
-                let cursor_column = position.character as usize;
-                let line_content = line.unwrap();
-                let (word_in_code, start_col, end_col) =
-                    get_word_in_line_col_position(line_content, cursor_column);
-                word_line = line_position as u32;
-                word_range = (start_col as u32, end_col as u32);
-                word.push_str(word_in_code.as_str());
-                break;
-            }
-
-            match get_hover_text(word) {
-                Some(hover_text_content) => {
-                    let result = Hover {
-                        contents: HoverContents::Markup(MarkupContent {
-                            kind: MarkupKind::Markdown,
-                            value: hover_text_content,
-                        }),
-                        range: Some(Range {
-                            start: Position {
-                                line: word_line,
-                                character: word_range.0,
-                            },
-                            end: Position {
-                                line: word_line,
-                                character: word_range.1,
-                            },
-                        }),
-                    };
-                    Ok(Some(result))
-                }
-                None => Ok(None),
+    let mut range = Range {
+        start: Position {
+            line: position.line,
+            character: start_col as u32,
+        },
+        end: Position {
+            line: position.line,
+            character: end_col as u32,
+        },
+    };
+    let hover_text = get_hover_text(word.clone()).or_else(|| {
+        let docs = get_docs(scan_tokens(source.clone()).tokens);
+        let doc_text = docs.get(&word).map(|doc| get_doc_hover_text(&word, doc));
+        match constant_at(&source, position) {
+            Some((label, value, constant_range)) => {
+                range = constant_range;
+                let value_text = get_value_hover_text(&label, &value);
+                Some(doc_text.unwrap_or_default() + &value_text)
             }
+            None => doc_text,
         }
-        Err(_) => Err(Error {
-            code: ErrorCode::InvalidParams,
-            message: String::from_utf8_lossy(NO_FILE_OR_DIRECTORY.as_bytes()),
-            data: None,
+    });
+
+    Ok(hover_text.map(|hover_text_content| Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: hover_text_content,
         }),
+        range: Some(range),
+    }))
+}
+
+/// The constant value under `position`, with what it is the value of and
+/// its range: a variable declared or read there, or the operation whose
+/// operator is there.
+fn constant_at(source: &str, position: Position) -> Option<(String, Value, Range)> {
+    let line_index = LineIndex::new(source);
+    let offset = line_index.offset(position)?;
+    let root = parse(source).syntax();
+    let token = root.token_at_offset(offset)?;
+    let node = token.parent();
+    let expr = match node.kind() {
+        // A literal initializer already shows its value.
+        NodeKind::Name => match node.parent().and_then(VarDecl::cast)?.initializer()? {
+            Expr::Literal(_) => return None,
+            initializer => initializer,
+        },
+        NodeKind::NameRef | NodeKind::BinaryExpr | NodeKind::PrefixExpr | NodeKind::ParenExpr => {
+            Expr::cast(node.clone())?
+        }
+        _ => return None,
+    };
+    let label = match Name::cast(node.clone()) {
+        Some(name) => name.text(),
+        None => node.text().trim().to_string(),
+    };
+    let resolution = Resolution::resolve(&root);
+    let value = ConstantFolder::new(&root, &resolution).fold(&expr)?;
+    Some((label, value, line_index.range(node.text_range())))
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::{TextDocumentIdentifier, TextDocumentPositionParams, Url};
+
+    use super::*;
+
+    /// Hovers `source` as the editor holds it, for a file never saved.
+    fn hover(source: &str, line: u32, character: u32) -> Option<String> {
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: Url::parse("file:///unsaved.severo").unwrap(),
+                },
+                position: Position { line, character },
+            },
+            work_done_progress_params: Default::default(),
+        };
+        let hover = hover_method(params, source.to_string()).unwrap()?;
+        match hover.contents {
+            HoverContents::Markup(markup) => Some(markup.value),
+            _ => None,
+        }
+    }
+
+    fn constant(source: &str, line: u32, character: u32) -> Option<(String, String)> {
+        constant_at(source, Position { line, character })
+            .map(|(label, value, _)| (label, format!("{:?}", value)))
+    }
+
+    fn pair(label: &str, value: &str) -> Option<(String, String)> {
+        Some((label.to_string(), value.to_string()))
+    }
+
+    #[test]
+    fn should_evaluate_constant_bindings() {
+        let source = "severo base = 2\nsevero total = 10 * 3 + base\nprint(total)\n";
+        assert_eq!(constant(source, 1, 9), pair("total", "32"));
+        assert_eq!(constant(source, 2, 8), pair("total", "32"));
+        assert_eq!(constant(source, 1, 22), pair("10 * 3 + base", "32"));
+        assert_eq!(constant(source, 1, 18), pair("10 * 3", "30"));
+        assert_eq!(constant(source, 1, 15), None);
+        assert_eq!(constant(source, 0, 8), None);
+    }
+
+    #[test]
+    fn should_hover_the_editor_text() {
+        let source = "severo base = 2\nsevero total = base * 3\n";
+        assert_eq!(
+            hover(source, 1, 9).as_deref(),
+            Some("\n```severo\ntotal = 6\n```")
+        );
+        assert_eq!(hover(source, 5, 0), None);
+    }
+
+    #[test]
+    fn should_hover_unsaved_doc_comments() {
+        let source = "/// How many there are.\nsevero count = 0\n";
+        assert_eq!(
+            hover(source, 1, 9).as_deref(),
+            Some("\n### count\n\nHow many there are.")
+        );
+    }
+
+    #[test]
+    fn should_skip_values_known_at_runtime() {
+        let source = "severo count = 0\ncount = count + 1\nsevero next = count + 1\n";
+        assert_eq!(constant(source, 2, 9), None);
+        assert_eq!(constant(source, 2, 20), None);
     }
 }
@@ -1,3 +1,5 @@
+use crate::spec::interpreter::Value;
+
 pub fn get_hover_text(word: String) -> Option<String> {
     match word.as_str() {
         "severo" => Some(VAR.to_string()),
@@ -6,6 +8,14 @@ pub fn get_hover_text(word: String) -> Option<String> {
     }
 }
 
+pub fn get_doc_hover_text(name: &str, doc: &str) -> String {
+    format!("\n### {}\n\n{}", name, doc)
+}
+
+pub fn get_value_hover_text(label: &str, value: &Value) -> String {
+    format!("\n```severo\n{} = {:?}\n```", label, value)
+}
+
 pub const VAR: &str = r#"
 ### severo
 
@@ -63,6 +73,19 @@ mod tests {
         assert_eq!(get_hover_text(word), expected);
     }
 
+    #[test]
+    fn test_get_doc_hover_text() {
+        let expected = "\n### count\n\nCounts things.".to_string();
+        assert_eq!(get_doc_hover_text("count", "Counts things."), expected);
+    }
+
+    #[test]
+    fn test_get_value_hover_text() {
+        let expected = "\n```severo\ngreeting = \"hi\"\n```".to_string();
+        let value = Value::String("hi".to_string());
+        assert_eq!(get_value_hover_text("greeting", &value), expected);
+    }
+
     #[test]
     fn test_get_hover_text_special_characters() {
         let word = "severo!".to_string();
@@ -1,17 +1,20 @@
 use tower_lsp::jsonrpc::Error;
 use tower_lsp::lsp_types::{
-    CompletionOptions, HoverProviderCapability, InitializeResult, ServerCapabilities, ServerInfo,
-    WorkDoneProgressOptions,
+    CallHierarchyServerCapability, CodeActionProviderCapability, CodeLensOptions,
+    CompletionOptions, DocumentOnTypeFormattingOptions, ExecuteCommandOptions,
+    FoldingRangeProviderCapability, HoverProviderCapability, InitializeResult, OneOf,
+    SelectionRangeProviderCapability, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, WorkDoneProgressOptions,
 };
 
-use crate::consts::{SERVER_NAME, SERVER_VERSION};
+use crate::consts::{RUN_FILE_COMMAND, SERVER_NAME, SERVER_VERSION};
 
 pub fn initialize() -> Result<InitializeResult, Error> {
     Ok(InitializeResult {
         capabilities: ServerCapabilities {
             position_encoding: None,
-            text_document_sync: None,
-            selection_range_provider: None,
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
             completion_provider: Some(CompletionOptions {
                 resolve_provider: None,
@@ -27,27 +30,37 @@ pub fn initialize() -> Result<InitializeResult, Error> {
             type_definition_provider: None,
             implementation_provider: None,
             references_provider: None,
-            document_highlight_provider: None,
+            document_highlight_provider: Some(OneOf::Left(true)),
             document_symbol_provider: None,
             workspace_symbol_provider: None,
-            code_action_provider: None,
-            code_lens_provider: None,
-            document_formatting_provider: None,
-            document_range_formatting_provider: None,
-            document_on_type_formatting_provider: None,
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: Some(false),
+            }),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                first_trigger_character: "}".to_string(),
+                more_trigger_character: Some(vec!["\n".to_string()]),
+            }),
             rename_provider: None,
             document_link_provider: None,
             color_provider: None,
-            folding_range_provider: None,
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
             declaration_provider: None,
-            execute_command_provider: None,
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec![RUN_FILE_COMMAND.to_string()],
+                work_done_progress_options: WorkDoneProgressOptions {
+                    work_done_progress: None,
+                },
+            }),
             workspace: None,
-            call_hierarchy_provider: None,
+            call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
             semantic_tokens_provider: None,
             moniker_provider: None,
             linked_editing_range_provider: None,
             inline_value_provider: None,
-            inlay_hint_provider: None,
+            inlay_hint_provider: Some(OneOf::Left(true)),
             diagnostic_provider: None,
             experimental: None,
         },
@@ -0,0 +1,157 @@
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams};
+
+use crate::spec::{
+    ast::{AstNode, CallExpr, Expr, VarDecl},
+    inference::{Inference, Type},
+    line_index::LineIndex,
+    parser::parse,
+    resolver::Resolution,
+    syntax::NodeKind,
+};
+
+pub fn inlay_hint_method(
+    params: InlayHintParams,
+    source: String,
+) -> Result<Option<Vec<InlayHint>>, Error> {
+    let line_index = LineIndex::new(&source);
+    let start = line_index.offset(params.range.start).unwrap_or(0);
+    let end = line_index.offset(params.range.end).unwrap_or(source.len());
+    let hints = get_inlay_hints(&source)
+        .into_iter()
+        .filter(|hint| start <= hint.offset && hint.offset <= end)
+        .map(|hint| InlayHint {
+            position: line_index.position(hint.offset),
+            label: InlayHintLabel::String(hint.label),
+            kind: Some(hint.kind),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: Some(hint.kind == InlayHintKind::PARAMETER),
+            data: None,
+        })
+        .collect();
+    Ok(Some(hints))
+}
+
+pub struct Hint {
+    pub offset: usize,
+    pub label: String,
+    pub kind: InlayHintKind,
+}
+
+pub fn get_inlay_hints(source: &str) -> Vec<Hint> {
+    let root = parse(source).syntax();
+    let resolution = Resolution::resolve(&root);
+    let inference = Inference::infer(&root, &resolution);
+    let mut hints = Vec::new();
+
+    for node in root.descendants() {
+        match node.kind() {
+            NodeKind::VarDecl => {
+                let declaration = VarDecl::cast(node).expect("a declaration");
+                let (Some(name), Some(initializer)) =
+                    (declaration.name(), declaration.initializer())
+                else {
+                    continue;
+                };
+                let value = inference.type_of(initializer.text_range());
+                if matches!(
+                    value,
+                    Type::Number | Type::String | Type::Bool | Type::Nil | Type::Instance(_)
+                ) {
+                    hints.push(Hint {
+                        offset: name.text_range().end,
+                        label: format!(": {}", value),
+                        kind: InlayHintKind::TYPE,
+                    });
+                }
+            }
+            NodeKind::CallExpr => {
+                let call = CallExpr::cast(node).expect("a call");
+                let Some(callee) = call.callee() else {
+                    continue;
+                };
+                let params = match inference.type_of(callee.text_range()) {
+                    Type::Function { params, .. }
+                    | Type::Class {
+                        params: Some(params),
+                        ..
+                    } => params.clone(),
+                    _ => continue,
+                };
+                let args = call.arg_list().into_iter().flat_map(|args| args.args());
+                for (param, arg) in params.iter().zip(args) {
+                    if !is_named_like(&arg, param) {
+                        hints.push(Hint {
+                            offset: arg.text_range().start,
+                            label: format!("{}:", param),
+                            kind: InlayHintKind::PARAMETER,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    hints.sort_by_key(|hint| hint.offset);
+    hints
+}
+
+/// Whether the argument already reads as the parameter it is passed to.
+fn is_named_like(arg: &Expr, param: &str) -> bool {
+    match arg {
+        Expr::NameRef(name_ref) => name_ref.text() == param,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hints(source: &str) -> Vec<(usize, String)> {
+        get_inlay_hints(source)
+            .into_iter()
+            .map(|hint| (hint.offset, hint.label))
+            .collect()
+    }
+
+    #[test]
+    fn should_hint_inferred_types() {
+        let source =
+            "severo count = 10\nsevero name = \"Ana\"\nsevero other = count\nsevero f = fun() {}";
+        assert_eq!(
+            hints(source),
+            vec![
+                (12, ": number".to_string()),
+                (29, ": string".to_string()),
+                (50, ": number".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_hint_parameter_names() {
+        let source = concat!(
+            "severo greet = fun(name, greeting) {}\n",
+            "severo greeting = \"Hi\"\n",
+            "greet(\"Ana\", greeting)\n",
+            "class A {\n",
+            "    init(x) {}\n",
+            "}\n",
+            "severo a = A(1)\n",
+        );
+        let call = source.find("greet(\"").unwrap() + 6;
+        let construct = source.find("A(1)").unwrap() + 2;
+        assert_eq!(
+            hints(source),
+            vec![
+                (53, ": string".to_string()),
+                (call, "name:".to_string()),
+                (construct - 5, ": A".to_string()),
+                (construct, "x:".to_string()),
+            ]
+        );
+    }
+}
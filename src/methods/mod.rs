@@ -1,4 +1,15 @@
+pub mod call_hierarchy;
+pub mod code_action;
+pub mod code_lens;
 pub mod completion;
+pub mod diagnostics;
+pub mod document_highlight;
 pub mod errors;
+pub mod folding_range;
+pub mod formatting;
 pub mod hover;
 pub mod initialize;
+pub mod inlay_hint;
+pub mod refactor;
+pub mod run_file;
+pub mod selection_range;
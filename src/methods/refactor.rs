@@ -0,0 +1,584 @@
+use tower_lsp::lsp_types::CodeActionKind;
+
+use crate::spec::{
+    ast::{AstNode, BinaryExpr, CallExpr, Expr, FieldExpr, Stmt, VarDecl},
+    builtin_functions::get_builtin_functions,
+    formatter::FormatOptions,
+    line_index::LineIndex,
+    parser::parse,
+    resolver::{Resolution, SymbolKind},
+    syntax::{NodeKind, SyntaxElement, SyntaxNode, TextRange},
+    types::TokenType,
+};
+
+use super::code_action::{has_side_effects, removal_range};
+
+/// A refactoring offered for the selected `range`.
+pub struct Refactor {
+    pub title: String,
+    pub kind: CodeActionKind,
+    pub edits: Vec<(TextRange, String)>,
+}
+
+pub fn get_refactors(
+    source: &str,
+    line_index: &LineIndex,
+    range: TextRange,
+    options: &FormatOptions,
+) -> Vec<Refactor> {
+    let root = parse(source).syntax();
+    let resolution = Resolution::resolve(&root);
+    [
+        extract_variable(source, line_index, &root, &resolution, range),
+        extract_function(source, line_index, &root, &resolution, range, options),
+        inline_variable(source, line_index, &root, &resolution, range),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn extract_variable(
+    source: &str,
+    line_index: &LineIndex,
+    root: &SyntaxNode,
+    resolution: &Resolution,
+    range: TextRange,
+) -> Option<Refactor> {
+    let range = trim_range(source, range);
+    if range.is_empty() {
+        return None;
+    }
+    let covering = match root.covering_element(range) {
+        SyntaxElement::Node(node) => node,
+        SyntaxElement::Token(token) => token.parent(),
+    };
+    let expr = covering
+        .ancestors()
+        .take_while(|node| node.text_range() == range)
+        .find_map(Expr::cast)?;
+    let expr = expr.syntax();
+    if !can_extract(expr) {
+        return None;
+    }
+    let statement = enclosing_statement(expr)?;
+    let hoisted = expr
+        .ancestors()
+        .take_while(|node| *node != statement)
+        .any(|node| match node.kind() {
+            NodeKind::ElifBranch | NodeKind::WhileStmt | NodeKind::ForStmt => true,
+            NodeKind::BinaryExpr => is_short_circuited(&node, expr),
+            _ => false,
+        });
+    if hoisted || matches!(statement.kind(), NodeKind::WhileStmt | NodeKind::ForStmt) {
+        return None;
+    }
+
+    let name = fresh_name(resolution, "value");
+    let statement_start = statement.text_range().start;
+    let indent = line_indent(source, line_index, statement_start);
+    let new_text = format!(
+        "severo {} = {}\n{}{}{}",
+        name,
+        expr.text(),
+        indent,
+        &source[statement_start..range.start],
+        name
+    );
+    Some(Refactor {
+        title: "Extract to `severo` variable".to_string(),
+        kind: CodeActionKind::REFACTOR_EXTRACT,
+        edits: vec![(TextRange::new(statement_start, range.end), new_text)],
+    })
+}
+
+fn can_extract(expr: &SyntaxNode) -> bool {
+    let Some(parent) = expr.parent() else {
+        return false;
+    };
+    match parent.kind() {
+        NodeKind::ExprStmt | NodeKind::SuperExpr => false,
+        NodeKind::AssignExpr => expr.prev_sibling().is_some(),
+        NodeKind::CallExpr => CallExpr::cast(parent)
+            .and_then(|call| call.callee())
+            .is_none_or(|callee| callee.syntax() != expr),
+        NodeKind::FieldExpr => FieldExpr::cast(parent)
+            .and_then(|field| field.name_ref())
+            .is_none_or(|name_ref| name_ref.syntax() != expr),
+        _ => true,
+    }
+}
+
+/// Whether `expr` is only evaluated depending on the left side of an `and` or
+/// `or`, so that hoisting it would run its side effects unconditionally.
+fn is_short_circuited(binary: &SyntaxNode, expr: &SyntaxNode) -> bool {
+    let Some(binary) = BinaryExpr::cast(binary.clone()) else {
+        return false;
+    };
+    let short_circuits = binary
+        .op_token()
+        .is_some_and(|op| matches!(op.kind(), TokenType::And | TokenType::Or));
+    let in_rhs = binary
+        .rhs()
+        .is_some_and(|rhs| rhs.text_range().contains_range(expr.text_range()));
+    short_circuits && in_rhs && has_side_effects(expr)
+}
+
+fn extract_function(
+    source: &str,
+    line_index: &LineIndex,
+    root: &SyntaxNode,
+    resolution: &Resolution,
+    range: TextRange,
+    options: &FormatOptions,
+) -> Option<Refactor> {
+    let range = trim_range(source, range);
+    let statements = selected_statements(root, range)?;
+    let first = statements.first()?.text_range();
+    let last = statements.last()?.text_range();
+    if first.start != range.start || last.end != range.end {
+        return None;
+    }
+    let escapes = statements
+        .iter()
+        .flat_map(SyntaxNode::descendants)
+        .any(|node| {
+            matches!(
+                node.kind(),
+                NodeKind::ReturnStmt | NodeKind::ThisExpr | NodeKind::SuperExpr
+            ) && !node
+                .ancestors()
+                .take_while(|ancestor| range.contains_range(ancestor.text_range()))
+                .any(|ancestor| matches!(ancestor.kind(), NodeKind::FunExpr | NodeKind::Method))
+        });
+    if escapes {
+        return None;
+    }
+
+    let is_declared_inside =
+        |symbol: usize| range.contains_range(resolution.symbols[symbol].declaration_range);
+    let is_global = |symbol: usize| resolution.symbols[symbol].scope == 0;
+    let inside = || {
+        resolution
+            .references
+            .iter()
+            .filter(|reference| range.contains_range(reference.range))
+            .filter_map(|reference| Some((reference, reference.symbol?)))
+    };
+
+    // Locals of the enclosing function are out of reach of the extracted
+    // one, so those it writes are passed in too, even if it never reads them.
+    let mut params: Vec<usize> = Vec::new();
+    for (_, symbol) in inside() {
+        if !is_declared_inside(symbol) && !is_global(symbol) && !params.contains(&symbol) {
+            params.push(symbol);
+        }
+    }
+    let used_after = |symbol: usize| {
+        resolution
+            .references_to(symbol)
+            .any(|reference| reference.range.start >= range.end)
+    };
+    let mut outputs: Vec<usize> = resolution
+        .symbols
+        .iter()
+        .enumerate()
+        .map(|(id, _)| id)
+        .filter(|id| is_declared_inside(*id) && used_after(*id))
+        .collect();
+    // A loop around the selection runs it again, so variables declared
+    // outside of it are also read later by any read inside the loop.
+    let loops: Vec<TextRange> = statements[0]
+        .ancestors()
+        .take_while(|node| !matches!(node.kind(), NodeKind::FunExpr | NodeKind::Method))
+        .filter(|node| matches!(node.kind(), NodeKind::WhileStmt | NodeKind::ForStmt))
+        .map(|node| node.text_range())
+        .collect();
+    let read_in_loop = |symbol: usize| {
+        resolution.references_to(symbol).any(|reference| {
+            !reference.is_write
+                && loops
+                    .iter()
+                    .any(|range| range.contains_range(reference.range))
+        })
+    };
+    for (_, symbol) in inside().filter(|(reference, _)| reference.is_write) {
+        let read_again = used_after(symbol) || read_in_loop(symbol);
+        if !is_global(symbol) && read_again && !outputs.contains(&symbol) {
+            outputs.push(symbol);
+        }
+    }
+    if outputs.len() > 1 {
+        return None;
+    }
+
+    let name = fresh_name(resolution, "extracted");
+    let params = params
+        .iter()
+        .map(|symbol| resolution.symbols[*symbol].name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let call = format!("{}({})", name, params);
+    let call = match outputs.first() {
+        Some(&output) if is_declared_inside(output) => {
+            format!("severo {} = {}", resolution.symbols[output].name, call)
+        }
+        Some(&output) => format!("{} = {}", resolution.symbols[output].name, call),
+        None => call,
+    };
+
+    let indent = options.indent(1);
+    let base_indent = line_indent(source, line_index, range.start);
+    let mut function = format!("severo {} = fun({}) {{\n", name, params);
+    for line in source[range.start..range.end].lines() {
+        let line = line.strip_prefix(base_indent).unwrap_or(line);
+        match line.trim().is_empty() {
+            true => function.push('\n'),
+            false => function.push_str(&format!("{}{}\n", indent, line)),
+        }
+    }
+    if let Some(&output) = outputs.first() {
+        let output = &resolution.symbols[output].name;
+        function.push_str(&format!("{}return {}\n", indent, output));
+    }
+    function.push_str("}\n\n");
+
+    // The function goes right before the top-level statement holding the
+    // selection, so that it only closes over globals.
+    let top_level = statements[0]
+        .ancestors()
+        .find(|node| node.parent().is_some_and(|parent| parent == *root))?;
+    let insert_at = top_level.text_range().start;
+    let new_text = format!("{}{}{}", function, &source[insert_at..range.start], call);
+    Some(Refactor {
+        title: "Extract to function".to_string(),
+        kind: CodeActionKind::REFACTOR_EXTRACT,
+        edits: vec![(TextRange::new(insert_at, range.end), new_text)],
+    })
+}
+
+/// The statements of a single block or file that overlap `range`.
+fn selected_statements(root: &SyntaxNode, range: TextRange) -> Option<Vec<SyntaxNode>> {
+    if range.is_empty() {
+        return None;
+    }
+    let covering = match root.covering_element(range) {
+        SyntaxElement::Node(node) => node,
+        SyntaxElement::Token(token) => token.parent(),
+    };
+    let container = covering
+        .ancestors()
+        .filter(|node| node.text_range() != range)
+        .find(|node| matches!(node.kind(), NodeKind::SourceFile | NodeKind::Block))?;
+    let statements: Vec<SyntaxNode> = container
+        .children()
+        .filter_map(Stmt::cast)
+        .map(|statement| statement.syntax().clone())
+        .filter(|statement| {
+            let statement = statement.text_range();
+            statement.start < range.end && range.start < statement.end
+        })
+        .collect();
+    match statements.is_empty() {
+        true => None,
+        false => Some(statements),
+    }
+}
+
+fn inline_variable(
+    source: &str,
+    line_index: &LineIndex,
+    root: &SyntaxNode,
+    resolution: &Resolution,
+    range: TextRange,
+) -> Option<Refactor> {
+    let symbol_id = resolution.symbol_at(range.start)?;
+    let symbol = &resolution.symbols[symbol_id];
+    if symbol.kind != SymbolKind::Variable {
+        return None;
+    }
+    let declaration = root
+        .covering_element(symbol.declaration_range)
+        .into_node()
+        .and_then(VarDecl::cast)?;
+    let initializer = declaration.initializer()?;
+    let references: Vec<_> = resolution.references_to(symbol_id).collect();
+    if references.is_empty()
+        || references.iter().any(|reference| reference.is_write)
+        || has_side_effects(initializer.syntax())
+    {
+        return None;
+    }
+
+    // The names read by the initializer must mean the same at every use.
+    let reads: Vec<_> = resolution
+        .references
+        .iter()
+        .filter(|reference| initializer.text_range().contains_range(reference.range))
+        .collect();
+    let stable = reads.iter().all(|read| {
+        let reassigned = read.symbol.is_some_and(|read_symbol| {
+            resolution
+                .references_to(read_symbol)
+                .any(|reference| reference.is_write)
+        });
+        !reassigned
+            && references.iter().all(|reference| {
+                let visible = resolution
+                    .visible_symbols(reference.range.start)
+                    .into_iter()
+                    .find(|id| resolution.symbols[*id].name == read.name);
+                visible == read.symbol
+            })
+    });
+    if !stable {
+        return None;
+    }
+
+    let text = initializer.syntax().text();
+    let mut edits: Vec<(TextRange, String)> = references
+        .iter()
+        .map(|reference| {
+            let use_site = root
+                .covering_element(reference.range)
+                .parent()
+                .and_then(|name_ref| name_ref.parent());
+            let new_text = match use_site.is_some_and(|parent| needs_parens(&initializer, &parent))
+            {
+                true => format!("({})", text),
+                false => text.clone(),
+            };
+            (reference.range, new_text)
+        })
+        .collect();
+    edits.push((
+        removal_range(source, line_index, declaration.text_range()),
+        String::new(),
+    ));
+    Some(Refactor {
+        title: "Inline variable".to_string(),
+        kind: CodeActionKind::REFACTOR_INLINE,
+        edits,
+    })
+}
+
+fn needs_parens(initializer: &Expr, parent: &SyntaxNode) -> bool {
+    match initializer {
+        Expr::BinaryExpr(_) => matches!(
+            parent.kind(),
+            NodeKind::BinaryExpr | NodeKind::PrefixExpr | NodeKind::CallExpr | NodeKind::FieldExpr
+        ),
+        Expr::PrefixExpr(_) => matches!(parent.kind(), NodeKind::CallExpr | NodeKind::FieldExpr),
+        _ => false,
+    }
+}
+
+/// The statement of a block or file that contains `node`.
+pub fn enclosing_statement(node: &SyntaxNode) -> Option<SyntaxNode> {
+    node.ancestors().find(|node| {
+        node.parent()
+            .is_some_and(|parent| matches!(parent.kind(), NodeKind::SourceFile | NodeKind::Block))
+    })
+}
+
+/// The whitespace at the start of the line holding `offset`.
+pub fn line_indent<'a>(source: &'a str, line_index: &LineIndex, offset: usize) -> &'a str {
+    let line_start = line_index.line_start(line_index.location(offset).line);
+    let line = &source[line_start..offset];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// `base`, or `base` followed by a number, such that no declaration in the
+/// file nor a builtin already uses it.
+pub fn fresh_name(resolution: &Resolution, base: &str) -> String {
+    let builtins = get_builtin_functions();
+    let taken = |name: &str| {
+        resolution.symbols.iter().any(|symbol| symbol.name == name)
+            || builtins.iter().any(|builtin| builtin == name)
+    };
+    (1..)
+        .map(|index| match index {
+            1 => base.to_string(),
+            _ => format!("{}{}", base, index),
+        })
+        .find(|name| !taken(name))
+        .expect("an unused name")
+}
+
+fn trim_range(source: &str, range: TextRange) -> TextRange {
+    let text = &source[range.start..range.end];
+    let start = range.start + text.len() - text.trim_start().len();
+    TextRange::new(start, start.max(range.start + text.trim_end().len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::formatter::{apply_edits, Edit};
+
+    use super::*;
+
+    fn refactor(source: &str, selection: &str, title: &str) -> Option<String> {
+        let start = source.rfind(selection).unwrap();
+        let range = TextRange::new(start, start + selection.len());
+        get_refactors(
+            source,
+            &LineIndex::new(source),
+            range,
+            &FormatOptions::default(),
+        )
+        .into_iter()
+        .find(|refactor| refactor.title == title)
+        .map(|refactor| {
+            let edits: Vec<Edit> = refactor
+                .edits
+                .into_iter()
+                .map(|(range, new_text)| Edit { range, new_text })
+                .collect();
+            apply_edits(source, &edits)
+        })
+    }
+
+    fn extract(source: &str, selection: &str) -> Option<String> {
+        refactor(source, selection, "Extract to `severo` variable")
+    }
+
+    fn extract_function(source: &str, selection: &str) -> Option<String> {
+        refactor(source, selection, "Extract to function")
+    }
+
+    fn inline(source: &str, selection: &str) -> Option<String> {
+        refactor(source, selection, "Inline variable")
+    }
+
+    #[test]
+    fn should_extract_expressions_to_variables() {
+        assert_eq!(
+            extract("{\n    print(1 + 2 * 3)\n}", "2 * 3").as_deref(),
+            Some("{\n    severo value = 2 * 3\n    print(1 + value)\n}")
+        );
+        assert_eq!(
+            extract("severo value = 1\nprint(value + 2)", "value + 2").as_deref(),
+            Some("severo value = 1\nsevero value2 = value + 2\nprint(value2)")
+        );
+    }
+
+    #[test]
+    fn should_not_extract_what_would_change_meaning() {
+        assert_eq!(extract("print(1 + 2 * 3)", "1 + 2"), None);
+        assert_eq!(extract("print(1)", "print(1)"), None);
+        assert_eq!(extract("severo a = 1\na = 2", "a"), None);
+        assert_eq!(extract("severo a = 1\nprint(a.b)", "b"), None);
+        assert_eq!(extract("severo a = 1\nwhile a < 3 {\n}", "a < 3"), None);
+        assert_eq!(
+            extract("severo a = 1\nprint(a or clock())", "clock()"),
+            None
+        );
+    }
+
+    #[test]
+    fn should_extract_statements_to_functions() {
+        let source =
+            "severo main = fun(a) {\n    severo b = a * 2\n    print(b)\n    print(b + a)\n}\n";
+        assert_eq!(
+            extract_function(source, "severo b = a * 2\n    print(b)").as_deref(),
+            Some(concat!(
+                "severo extracted = fun(a) {\n",
+                "    severo b = a * 2\n",
+                "    print(b)\n",
+                "    return b\n",
+                "}\n\n",
+                "severo main = fun(a) {\n",
+                "    severo b = extracted(a)\n",
+                "    print(b + a)\n",
+                "}\n",
+            ))
+        );
+        let source = "{\n    severo a = 1\n    a = a + 1\n    print(a)\n}";
+        assert_eq!(
+            extract_function(source, "a = a + 1").as_deref(),
+            Some(concat!(
+                "severo extracted = fun(a) {\n",
+                "    a = a + 1\n",
+                "    return a\n",
+                "}\n\n",
+                "{\n",
+                "    severo a = 1\n",
+                "    a = extracted(a)\n",
+                "    print(a)\n",
+                "}",
+            ))
+        );
+        let source = "severo count = fun() {\n    severo i = 0\n    while i < 3 {\n        i = i + 1\n    }\n}\n";
+        assert_eq!(
+            extract_function(source, "i = i + 1").as_deref(),
+            Some(concat!(
+                "severo extracted = fun(i) {\n",
+                "    i = i + 1\n",
+                "    return i\n",
+                "}\n\n",
+                "severo count = fun() {\n",
+                "    severo i = 0\n",
+                "    while i < 3 {\n",
+                "        i = extracted(i)\n",
+                "    }\n",
+                "}\n",
+            ))
+        );
+        let source = "severo f = fun() {\n    severo x = 0\n    x = 5\n    print(x)\n}\nf()";
+        assert_eq!(
+            extract_function(source, "x = 5").as_deref(),
+            Some(concat!(
+                "severo extracted = fun(x) {\n",
+                "    x = 5\n",
+                "    return x\n",
+                "}\n\n",
+                "severo f = fun() {\n",
+                "    severo x = 0\n",
+                "    x = extracted(x)\n",
+                "    print(x)\n",
+                "}\n",
+                "f()",
+            ))
+        );
+        assert_eq!(
+            extract_function("severo a = 1\nprint(a)\n", "print(a)").as_deref(),
+            Some("severo a = 1\nsevero extracted = fun() {\n    print(a)\n}\n\nextracted()\n")
+        );
+    }
+
+    #[test]
+    fn should_not_extract_functions_that_would_change_meaning() {
+        let source = "severo f = fun() {\n    severo a = 1\n    severo b = 2\n    return a + b\n}";
+        assert_eq!(
+            extract_function(source, "severo a = 1\n    severo b = 2"),
+            None
+        );
+        assert_eq!(extract_function(source, "return a + b"), None);
+        assert_eq!(extract_function("print(1 + 2)", "1 + 2"), None);
+    }
+
+    #[test]
+    fn should_inline_variables() {
+        assert_eq!(
+            inline("severo a = 1 + 2\nprint(a * 3)\nprint(a)", "a").as_deref(),
+            Some("print((1 + 2) * 3)\nprint(1 + 2)")
+        );
+        assert_eq!(
+            inline("severo a = 1\nsevero b = a\nprint(b)", "b)").as_deref(),
+            Some("severo a = 1\nprint(a)")
+        );
+    }
+
+    #[test]
+    fn should_not_inline_when_unsafe() {
+        assert_eq!(inline("severo a = 1\na = 2\nprint(a)", "a"), None);
+        assert_eq!(inline("severo a = clock()\nprint(a)", "a"), None);
+        assert_eq!(
+            inline(
+                "severo b = 1\nsevero a = b\n{\n    severo b = 2\n    print(a)\n}",
+                "a ="
+            ),
+            None
+        );
+    }
+}
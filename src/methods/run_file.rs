@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::task::{spawn_blocking, JoinHandle};
+use tokio::time::sleep;
+use tower_lsp::jsonrpc::{Error, ErrorCode};
+use tower_lsp::lsp_types::{Diagnostic, MessageType, Url};
+use tower_lsp::Client;
+
+use crate::methods::{diagnostics::error_diagnostic, errors::MISSING_FILE_ARGUMENT};
+use crate::spec::{
+    interpreter::{Interpreter, RuntimeError},
+    line_index::LineIndex,
+    parser::parse,
+    scanner::scan_tokens,
+    syntax::TextRange,
+};
+
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A program running on a thread of its own. Dropping it stops the program,
+/// so a cancelled request does not leave it running.
+pub struct Run {
+    /// The lines the program prints, as it prints them.
+    pub output: UnboundedReceiver<String>,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Result<(), RuntimeError>>,
+}
+
+impl Run {
+    pub fn start(source: String) -> Run {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, output) = unbounded_channel();
+        let interpreter_stop = stop.clone();
+        let handle = spawn_blocking(move || {
+            let parse = parse(&source);
+            Interpreter::new()
+                .with_printer(move |line| {
+                    let _ = sender.send(line.to_string());
+                })
+                .with_stop(interpreter_stop)
+                .run(&parse.tree())
+        });
+        Run {
+            output,
+            stop,
+            handle,
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Waits for the program to end. The interpreter reports what goes wrong
+    /// in the program as runtime errors; should it still panic, the run
+    /// ends with an error at the start of the file instead of the panic
+    /// reaching the server.
+    pub async fn finish(&mut self) -> Result<(), RuntimeError> {
+        (&mut self.handle).await.unwrap_or_else(|error| {
+            let reason = match error.try_into_panic() {
+                Ok(panic) => panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned()),
+                Err(_) => None,
+            };
+            let message = match reason {
+                Some(reason) => format!("The interpreter crashed: {}", reason),
+                None => "The interpreter crashed".to_string(),
+            };
+            Err(RuntimeError {
+                message,
+                range: TextRange::default(),
+            })
+        })
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The file a `severo.runFile` command runs, its first argument.
+pub fn run_file_uri(arguments: &[Value]) -> Result<Url, Error> {
+    arguments
+        .first()
+        .and_then(|argument| serde_json::from_value(argument.clone()).ok())
+        .ok_or_else(|| Error {
+            code: ErrorCode::InvalidParams,
+            message: String::from_utf8_lossy(MISSING_FILE_ARGUMENT.as_bytes()),
+            data: None,
+        })
+}
+
+/// Runs `source`, logging what it prints to the client. Returns the
+/// diagnostic of the runtime error that ended it, if any.
+pub async fn run_file_method(client: &Client, source: String) -> Option<Diagnostic> {
+    if !parse(&source).errors.is_empty() || !scan_tokens(source.clone()).errors.is_empty() {
+        client
+            .show_message(MessageType::ERROR, "Fix the syntax errors to run the file")
+            .await;
+        return None;
+    }
+    let mut run = Run::start(source.clone());
+    let timeout = sleep(RUN_TIMEOUT);
+    tokio::pin!(timeout);
+    let mut timed_out = false;
+    loop {
+        tokio::select! {
+            line = run.output.recv() => match line {
+                Some(line) => client.log_message(MessageType::LOG, line).await,
+                None => break,
+            },
+            _ = &mut timeout, if !timed_out => {
+                timed_out = true;
+                run.stop();
+            }
+        }
+    }
+    match run.finish().await {
+        Ok(()) => None,
+        Err(_) if timed_out => {
+            let message = format!("Stopped after {} seconds", RUN_TIMEOUT.as_secs());
+            client.show_message(MessageType::WARNING, message).await;
+            None
+        }
+        Err(error) => {
+            client
+                .log_message(MessageType::ERROR, error.message.clone())
+                .await;
+            let range = LineIndex::new(&source).range(error.range);
+            Some(error_diagnostic(range, error.message))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    async fn output(run: &mut Run) -> Vec<String> {
+        let mut lines = Vec::new();
+        while let Some(line) = run.output.recv().await {
+            lines.push(line);
+        }
+        lines
+    }
+
+    #[tokio::test]
+    async fn should_stream_printed_lines() {
+        let mut run = Run::start("print(1)\nprint(\"two\", 3)".to_string());
+        assert_eq!(output(&mut run).await, vec!["1", "two 3"]);
+        assert_eq!(run.finish().await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn should_end_with_the_runtime_error() {
+        let source = "print(1)\nprint(1 + nil)";
+        let mut run = Run::start(source.to_string());
+        assert_eq!(output(&mut run).await, vec!["1"]);
+        let error = run.finish().await.unwrap_err();
+        assert_eq!(error.message, "Expected a number or a string, found nil");
+        assert_eq!(&source[error.range.start..error.range.end], "nil");
+    }
+
+    #[tokio::test]
+    async fn should_stop_endless_programs() {
+        let mut run = Run::start("while (true) {}".to_string());
+        run.stop();
+        let error = run.finish().await.unwrap_err();
+        assert_eq!(error.message, "The program was stopped");
+    }
+
+    #[tokio::test]
+    async fn should_end_with_an_error_when_the_interpreter_panics() {
+        let mut run = Run {
+            output: unbounded_channel().1,
+            stop: Arc::new(AtomicBool::new(false)),
+            handle: spawn_blocking(|| panic!("out of luck")),
+        };
+        let error = run.finish().await.unwrap_err();
+        assert_eq!(error.message, "The interpreter crashed: out of luck");
+        assert_eq!(error.range, TextRange::default());
+    }
+
+    #[test]
+    fn should_read_the_file_argument() {
+        let uri = Url::parse("file:///tmp/main.severo").unwrap();
+        assert_eq!(run_file_uri(&[json!(uri)]), Ok(uri));
+        assert!(run_file_uri(&[]).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use tower_lsp::jsonrpc::Error;
+use tower_lsp::lsp_types::{SelectionRange, SelectionRangeParams};
+
+use crate::spec::{
+    line_index::LineIndex,
+    parser::parse,
+    syntax::{SyntaxNode, TextRange},
+};
+
+pub fn selection_range_method(
+    params: SelectionRangeParams,
+    source: String,
+) -> Result<Option<Vec<SelectionRange>>, Error> {
+    let line_index = LineIndex::new(&source);
+    let root = parse(&source).syntax();
+    let selection_ranges = params
+        .positions
+        .into_iter()
+        .map(|position| {
+            let offset = line_index.offset(position).unwrap_or(source.len());
+            selection_ranges(&root, offset)
+                .into_iter()
+                .rev()
+                .fold(None, |parent, range| {
+                    Some(SelectionRange {
+                        range: line_index.range(range),
+                        parent: parent.map(Box::new),
+                    })
+                })
+                .unwrap_or_else(|| SelectionRange {
+                    range: line_index.range(TextRange::empty(offset)),
+                    parent: None,
+                })
+        })
+        .collect();
+    Ok(Some(selection_ranges))
+}
+
+/// The ranges of the token at `offset` and of every node around it, from the
+/// innermost out, without repeating a range.
+fn selection_ranges(root: &SyntaxNode, offset: usize) -> Vec<TextRange> {
+    let Some(token) = root.token_at_offset(offset) else {
+        return vec![root.text_range()];
+    };
+    let token_range = (!token.kind().is_trivia()).then(|| token.text_range());
+    let mut ranges: Vec<TextRange> = Vec::new();
+    for range in token_range
+        .into_iter()
+        .chain(token.parent().ancestors().map(|node| node.text_range()))
+    {
+        if ranges.last() != Some(&range) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selections(source: &str, offset: usize) -> Vec<&str> {
+        selection_ranges(&parse(source).syntax(), offset)
+            .into_iter()
+            .map(|range| &source[range.start..range.end])
+            .collect()
+    }
+
+    #[test]
+    fn should_expand_from_identifier_to_file() {
+        let source = "severo f = fun(a) {\n    print(a + 1)\n}\n";
+        assert_eq!(
+            selections(source, source.find("a +").unwrap()),
+            vec![
+                "a",
+                "a + 1",
+                "(a + 1)",
+                "print(a + 1)",
+                "{\n    print(a + 1)\n}",
+                "fun(a) {\n    print(a + 1)\n}",
+                "severo f = fun(a) {\n    print(a + 1)\n}",
+                source,
+            ]
+        );
+    }
+
+    #[test]
+    fn should_start_from_the_node_around_whitespace() {
+        let source = "print(1,  2)";
+        assert_eq!(selections(source, 9), vec!["(1,  2)", source]);
+    }
+}
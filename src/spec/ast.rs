@@ -0,0 +1,554 @@
+use super::{
+    syntax::{NodeKind, SyntaxElement, SyntaxNode, SyntaxToken, TextRange},
+    types::{Literal as LiteralValue, TokenType},
+};
+
+/// A typed view over a `SyntaxNode` of a given kind.
+pub trait AstNode: Sized {
+    fn can_cast(kind: NodeKind) -> bool;
+    fn cast(node: SyntaxNode) -> Option<Self>;
+    fn syntax(&self) -> &SyntaxNode;
+
+    fn text_range(&self) -> TextRange {
+        self.syntax().text_range()
+    }
+}
+
+macro_rules! ast_node {
+    ($name:ident) => {
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        pub struct $name(SyntaxNode);
+
+        impl AstNode for $name {
+            fn can_cast(kind: NodeKind) -> bool {
+                kind == NodeKind::$name
+            }
+
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                match Self::can_cast(node.kind()) {
+                    true => Some($name(node)),
+                    false => None,
+                }
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                &self.0
+            }
+        }
+    };
+}
+
+ast_node!(SourceFile);
+ast_node!(VarDecl);
+ast_node!(ClassDecl);
+ast_node!(Method);
+ast_node!(ExprStmt);
+ast_node!(IfStmt);
+ast_node!(ElifBranch);
+ast_node!(ElseBranch);
+ast_node!(WhileStmt);
+ast_node!(ForStmt);
+ast_node!(ReturnStmt);
+ast_node!(Block);
+ast_node!(Name);
+ast_node!(NameRef);
+ast_node!(ParamList);
+ast_node!(ArgList);
+ast_node!(Literal);
+ast_node!(BinaryExpr);
+ast_node!(PrefixExpr);
+ast_node!(AssignExpr);
+ast_node!(CallExpr);
+ast_node!(FieldExpr);
+ast_node!(ParenExpr);
+ast_node!(FunExpr);
+ast_node!(ThisExpr);
+ast_node!(SuperExpr);
+
+fn child<N: AstNode>(parent: &SyntaxNode) -> Option<N> {
+    parent.children().find_map(N::cast)
+}
+
+fn children<N: AstNode>(parent: &SyntaxNode) -> impl Iterator<Item = N> {
+    parent.children().filter_map(N::cast)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Stmt {
+    VarDecl(VarDecl),
+    ClassDecl(ClassDecl),
+    ExprStmt(ExprStmt),
+    IfStmt(IfStmt),
+    WhileStmt(WhileStmt),
+    ForStmt(ForStmt),
+    ReturnStmt(ReturnStmt),
+    Block(Block),
+}
+
+impl AstNode for Stmt {
+    fn can_cast(kind: NodeKind) -> bool {
+        matches!(
+            kind,
+            NodeKind::VarDecl
+                | NodeKind::ClassDecl
+                | NodeKind::ExprStmt
+                | NodeKind::IfStmt
+                | NodeKind::WhileStmt
+                | NodeKind::ForStmt
+                | NodeKind::ReturnStmt
+                | NodeKind::Block
+        )
+    }
+
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        let stmt = match node.kind() {
+            NodeKind::VarDecl => Stmt::VarDecl(VarDecl(node)),
+            NodeKind::ClassDecl => Stmt::ClassDecl(ClassDecl(node)),
+            NodeKind::ExprStmt => Stmt::ExprStmt(ExprStmt(node)),
+            NodeKind::IfStmt => Stmt::IfStmt(IfStmt(node)),
+            NodeKind::WhileStmt => Stmt::WhileStmt(WhileStmt(node)),
+            NodeKind::ForStmt => Stmt::ForStmt(ForStmt(node)),
+            NodeKind::ReturnStmt => Stmt::ReturnStmt(ReturnStmt(node)),
+            NodeKind::Block => Stmt::Block(Block(node)),
+            _ => return None,
+        };
+        Some(stmt)
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Stmt::VarDecl(node) => node.syntax(),
+            Stmt::ClassDecl(node) => node.syntax(),
+            Stmt::ExprStmt(node) => node.syntax(),
+            Stmt::IfStmt(node) => node.syntax(),
+            Stmt::WhileStmt(node) => node.syntax(),
+            Stmt::ForStmt(node) => node.syntax(),
+            Stmt::ReturnStmt(node) => node.syntax(),
+            Stmt::Block(node) => node.syntax(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expr {
+    Literal(Literal),
+    NameRef(NameRef),
+    BinaryExpr(BinaryExpr),
+    PrefixExpr(PrefixExpr),
+    AssignExpr(AssignExpr),
+    CallExpr(CallExpr),
+    FieldExpr(FieldExpr),
+    ParenExpr(ParenExpr),
+    FunExpr(FunExpr),
+    ThisExpr(ThisExpr),
+    SuperExpr(SuperExpr),
+}
+
+impl AstNode for Expr {
+    fn can_cast(kind: NodeKind) -> bool {
+        matches!(
+            kind,
+            NodeKind::Literal
+                | NodeKind::NameRef
+                | NodeKind::BinaryExpr
+                | NodeKind::PrefixExpr
+                | NodeKind::AssignExpr
+                | NodeKind::CallExpr
+                | NodeKind::FieldExpr
+                | NodeKind::ParenExpr
+                | NodeKind::FunExpr
+                | NodeKind::ThisExpr
+                | NodeKind::SuperExpr
+        )
+    }
+
+    fn cast(node: SyntaxNode) -> Option<Self> {
+        let expr = match node.kind() {
+            NodeKind::Literal => Expr::Literal(Literal(node)),
+            NodeKind::NameRef => Expr::NameRef(NameRef(node)),
+            NodeKind::BinaryExpr => Expr::BinaryExpr(BinaryExpr(node)),
+            NodeKind::PrefixExpr => Expr::PrefixExpr(PrefixExpr(node)),
+            NodeKind::AssignExpr => Expr::AssignExpr(AssignExpr(node)),
+            NodeKind::CallExpr => Expr::CallExpr(CallExpr(node)),
+            NodeKind::FieldExpr => Expr::FieldExpr(FieldExpr(node)),
+            NodeKind::ParenExpr => Expr::ParenExpr(ParenExpr(node)),
+            NodeKind::FunExpr => Expr::FunExpr(FunExpr(node)),
+            NodeKind::ThisExpr => Expr::ThisExpr(ThisExpr(node)),
+            NodeKind::SuperExpr => Expr::SuperExpr(SuperExpr(node)),
+            _ => return None,
+        };
+        Some(expr)
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expr::Literal(node) => node.syntax(),
+            Expr::NameRef(node) => node.syntax(),
+            Expr::BinaryExpr(node) => node.syntax(),
+            Expr::PrefixExpr(node) => node.syntax(),
+            Expr::AssignExpr(node) => node.syntax(),
+            Expr::CallExpr(node) => node.syntax(),
+            Expr::FieldExpr(node) => node.syntax(),
+            Expr::ParenExpr(node) => node.syntax(),
+            Expr::FunExpr(node) => node.syntax(),
+            Expr::ThisExpr(node) => node.syntax(),
+            Expr::SuperExpr(node) => node.syntax(),
+        }
+    }
+}
+
+impl SourceFile {
+    pub fn statements(&self) -> impl Iterator<Item = Stmt> {
+        children(&self.0)
+    }
+}
+
+impl VarDecl {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn initializer(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl ClassDecl {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn superclass(&self) -> Option<NameRef> {
+        child(&self.0)
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = Method> {
+        children(&self.0)
+    }
+}
+
+impl Method {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn param_list(&self) -> Option<ParamList> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl ExprStmt {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl IfStmt {
+    pub fn condition(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn then_branch(&self) -> Option<Block> {
+        child(&self.0)
+    }
+
+    pub fn elif_branches(&self) -> impl Iterator<Item = ElifBranch> {
+        children(&self.0)
+    }
+
+    pub fn else_branch(&self) -> Option<ElseBranch> {
+        child(&self.0)
+    }
+}
+
+impl ElifBranch {
+    pub fn condition(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn block(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl ElseBranch {
+    pub fn block(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl WhileStmt {
+    pub fn condition(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl ForStmt {
+    /// The part of the header between `(` and the first `;`.
+    pub fn initializer(&self) -> Option<Stmt> {
+        self.header_part(0).and_then(Stmt::cast)
+    }
+
+    pub fn condition(&self) -> Option<Expr> {
+        self.header_part(1).and_then(Expr::cast)
+    }
+
+    pub fn increment(&self) -> Option<Expr> {
+        self.header_part(2).and_then(Expr::cast)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+
+    fn header_part(&self, index: usize) -> Option<SyntaxNode> {
+        let mut part = 0;
+        for element in self.0.children_with_tokens() {
+            match element {
+                SyntaxElement::Token(token)
+                    if matches!(token.kind(), TokenType::Semicolon | TokenType::RightParen) =>
+                {
+                    part += 1;
+                }
+                SyntaxElement::Node(node) if node.kind() == NodeKind::Block => break,
+                SyntaxElement::Node(node) if part == index => return Some(node),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl ReturnStmt {
+    pub fn value(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> impl Iterator<Item = Stmt> {
+        children(&self.0)
+    }
+
+    pub fn l_brace(&self) -> Option<SyntaxToken> {
+        self.0.first_token_of(TokenType::LeftBrace)
+    }
+
+    pub fn r_brace(&self) -> Option<SyntaxToken> {
+        self.0.first_token_of(TokenType::RightBrace)
+    }
+}
+
+impl Name {
+    pub fn ident_token(&self) -> Option<SyntaxToken> {
+        self.0.first_token_of(TokenType::Identifier)
+    }
+
+    pub fn text(&self) -> String {
+        self.ident_token()
+            .map(|token| token.text().to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl NameRef {
+    pub fn token(&self) -> Option<SyntaxToken> {
+        self.0
+            .tokens()
+            .find(|token| matches!(token.kind(), TokenType::Identifier | TokenType::Print))
+    }
+
+    pub fn text(&self) -> String {
+        self.token()
+            .map(|token| token.text().to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl ParamList {
+    pub fn params(&self) -> impl Iterator<Item = Name> {
+        children(&self.0)
+    }
+}
+
+impl ArgList {
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0)
+    }
+
+    pub fn r_paren(&self) -> Option<SyntaxToken> {
+        self.0.first_token_of(TokenType::RightParen)
+    }
+}
+
+impl Literal {
+    pub fn token(&self) -> Option<SyntaxToken> {
+        self.0.tokens().find(|token| !token.kind().is_trivia())
+    }
+
+    pub fn value(&self) -> Option<LiteralValue> {
+        self.token().and_then(|token| token.literal().cloned())
+    }
+}
+
+impl BinaryExpr {
+    pub fn lhs(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+
+    pub fn op_token(&self) -> Option<SyntaxToken> {
+        self.0.tokens().find(|token| !token.kind().is_trivia())
+    }
+}
+
+impl PrefixExpr {
+    pub fn op_token(&self) -> Option<SyntaxToken> {
+        self.0.tokens().find(|token| !token.kind().is_trivia())
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl AssignExpr {
+    pub fn target(&self) -> Option<Expr> {
+        children(&self.0).next()
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn arg_list(&self) -> Option<ArgList> {
+        child(&self.0)
+    }
+}
+
+impl FieldExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    /// The property name after the `.`.
+    pub fn name_ref(&self) -> Option<NameRef> {
+        let dot = self.0.first_token_of(TokenType::Dot)?;
+        children(&self.0)
+            .find(|name_ref: &NameRef| name_ref.text_range().start >= dot.text_range().end)
+    }
+}
+
+impl ParenExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl FunExpr {
+    pub fn param_list(&self) -> Option<ParamList> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl SuperExpr {
+    pub fn name_ref(&self) -> Option<NameRef> {
+        child(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::parser::parse;
+
+    use super::*;
+
+    #[test]
+    fn should_give_a_typed_view_of_declarations() {
+        let tree = parse("severo sum = fun(a, b) {\n  return a + b\n}\nprint(sum(1, 2))").tree();
+        let statements: Vec<Stmt> = tree.statements().collect();
+        assert_eq!(statements.len(), 2);
+
+        let Stmt::VarDecl(var_decl) = &statements[0] else {
+            panic!("expected a declaration");
+        };
+        assert_eq!(var_decl.name().unwrap().text(), "sum");
+        let Some(Expr::FunExpr(fun_expr)) = var_decl.initializer() else {
+            panic!("expected a function");
+        };
+        let params: Vec<String> = fun_expr
+            .param_list()
+            .unwrap()
+            .params()
+            .map(|param| param.text())
+            .collect();
+        assert_eq!(params, vec!["a", "b"]);
+        let body: Vec<Stmt> = fun_expr.body().unwrap().statements().collect();
+        let Stmt::ReturnStmt(return_stmt) = &body[0] else {
+            panic!("expected a return");
+        };
+        let Some(Expr::BinaryExpr(binary)) = return_stmt.value() else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(binary.op_token().unwrap().kind(), TokenType::Plus);
+        assert_eq!(binary.lhs().unwrap().syntax().text(), "a");
+        assert_eq!(binary.rhs().unwrap().syntax().text(), "b");
+    }
+
+    #[test]
+    fn should_give_a_typed_view_of_calls_and_fields() {
+        let tree = parse("this.name.first = super.build(1)").tree();
+        let Some(Stmt::ExprStmt(stmt)) = tree.statements().next() else {
+            panic!("expected an expression statement");
+        };
+        let Some(Expr::AssignExpr(assign)) = stmt.expr() else {
+            panic!("expected an assignment");
+        };
+        let Some(Expr::FieldExpr(field)) = assign.target() else {
+            panic!("expected a field");
+        };
+        assert_eq!(field.name_ref().unwrap().text(), "first");
+        assert_eq!(field.receiver().unwrap().syntax().text(), "this.name");
+        let Some(Expr::CallExpr(call)) = assign.value() else {
+            panic!("expected a call");
+        };
+        assert_eq!(call.arg_list().unwrap().args().count(), 1);
+        let Some(Expr::SuperExpr(super_expr)) = call.callee() else {
+            panic!("expected super");
+        };
+        assert_eq!(super_expr.name_ref().unwrap().text(), "build");
+    }
+
+    #[test]
+    fn should_give_a_typed_view_of_a_for_header() {
+        let tree = parse("for (; i < 3; i = i + 1) { print(i) }").tree();
+        let Some(Stmt::ForStmt(for_stmt)) = tree.statements().next() else {
+            panic!("expected a for loop");
+        };
+        assert!(for_stmt.initializer().is_none());
+        assert_eq!(for_stmt.condition().unwrap().syntax().text(), "i < 3");
+        assert_eq!(for_stmt.increment().unwrap().syntax().text(), "i = i + 1");
+        assert_eq!(for_stmt.body().unwrap().statements().count(), 1);
+    }
+}
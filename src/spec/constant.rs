@@ -0,0 +1,154 @@
+//! Folds expressions built only from literals, operators and constant
+//! `severo` bindings, i.e. variables that are never assigned again.
+
+use super::{
+    ast::{AstNode, Expr, NameRef, VarDecl},
+    interpreter::{Interpreter, Value},
+    resolver::{Resolution, SymbolKind},
+    syntax::{NodeKind, SyntaxNode},
+};
+
+/// How many bindings deep a constant may refer to other constants.
+const MAX_DEPTH: usize = 32;
+
+pub struct ConstantFolder<'a> {
+    root: &'a SyntaxNode,
+    resolution: &'a Resolution,
+}
+
+impl<'a> ConstantFolder<'a> {
+    pub fn new(root: &'a SyntaxNode, resolution: &'a Resolution) -> Self {
+        ConstantFolder { root, resolution }
+    }
+
+    /// The value of `expr`, or `None` when it is not constant or evaluating
+    /// it fails.
+    pub fn fold(&self, expr: &Expr) -> Option<Value> {
+        self.fold_at_depth(expr, 0)
+    }
+
+    fn fold_at_depth(&self, expr: &Expr, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let mut interpreter = Interpreter::new();
+        let env = interpreter.globals();
+        for node in std::iter::once(expr.syntax().clone()).chain(expr.syntax().descendants()) {
+            match node.kind() {
+                NodeKind::Literal
+                | NodeKind::BinaryExpr
+                | NodeKind::PrefixExpr
+                | NodeKind::ParenExpr => {}
+                NodeKind::NameRef => {
+                    let name_ref = NameRef::cast(node)?;
+                    let value = self.binding_value(&name_ref, depth)?;
+                    env.define(&name_ref.text(), value);
+                }
+                _ => return None,
+            }
+        }
+        interpreter.evaluate(expr, &env).ok()
+    }
+
+    /// The value of the variable `name_ref` reads, if it is constant.
+    fn binding_value(&self, name_ref: &NameRef, depth: usize) -> Option<Value> {
+        let range = name_ref.syntax().text_range();
+        let reference = self
+            .resolution
+            .references
+            .iter()
+            .find(|reference| reference.range == range)?;
+        if reference.is_write || reference.before_declaration {
+            return None;
+        }
+        let symbol_id = reference.symbol?;
+        let symbol = &self.resolution.symbols[symbol_id];
+        let assigned = self
+            .resolution
+            .references_to(symbol_id)
+            .any(|reference| reference.is_write);
+        if symbol.kind != SymbolKind::Variable || assigned {
+            return None;
+        }
+        let initializer = self
+            .root
+            .descendants()
+            .filter(|node| node.text_range() == symbol.declaration_range)
+            .find_map(VarDecl::cast)?
+            .initializer()?;
+        self.fold_at_depth(&initializer, depth + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::{ast::Stmt, parser::parse};
+
+    /// Folds the initializer of the last declaration in `source`.
+    fn fold_last(source: &str) -> Option<Value> {
+        let root = parse(source).syntax();
+        let resolution = Resolution::resolve(&root);
+        let initializer = root
+            .descendants()
+            .filter_map(VarDecl::cast)
+            .last()
+            .and_then(|decl| decl.initializer())?;
+        ConstantFolder::new(&root, &resolution).fold(&initializer)
+    }
+
+    #[test]
+    fn should_fold_literals_and_operators() {
+        assert_eq!(
+            fold_last("severo a = (1 + 2) * -3"),
+            Some(Value::Number(-9.0))
+        );
+        assert_eq!(
+            fold_last("severo a = \"ab\" + \"c\""),
+            Some(Value::String("abc".to_string()))
+        );
+        assert_eq!(fold_last("severo a = !(1 < 2)"), Some(Value::Bool(false)));
+    }
+
+    #[test]
+    fn should_fold_constant_bindings() {
+        let source = "severo base = 2\nsevero step = base * 4\nsevero total = 10 * 3 + step";
+        assert_eq!(fold_last(source), Some(Value::Number(38.0)));
+    }
+
+    #[test]
+    fn should_not_fold_reassigned_or_unknown_values() {
+        assert_eq!(
+            fold_last("severo base = 2\nbase = 5\nsevero total = base + 1"),
+            None
+        );
+        assert_eq!(fold_last("severo total = missing + 1"), None);
+        assert_eq!(
+            fold_last("severo f = fun() { return 1 }\nsevero total = f() + 1"),
+            None
+        );
+        assert_eq!(
+            fold_last("severo add = fun(n) {\n    severo total = n + 1\n}"),
+            None
+        );
+        assert_eq!(fold_last("severo total = 1 + nil"), None);
+    }
+
+    #[test]
+    fn should_fold_expression_statements() {
+        let root = parse("severo a = 4\na / 2").syntax();
+        let resolution = Resolution::resolve(&root);
+        let expr = root
+            .children()
+            .filter_map(Stmt::cast)
+            .find_map(|stmt| match stmt {
+                Stmt::ExprStmt(statement) => statement.expr(),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            ConstantFolder::new(&root, &resolution).fold(&expr),
+            Some(Value::Number(2.0))
+        );
+    }
+}
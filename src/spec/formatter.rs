@@ -0,0 +1,544 @@
+use super::{
+    helpers::unescape,
+    parser::parse,
+    scanner::scan_tokens,
+    syntax::{NodeKind, SyntaxNode, SyntaxToken, TextRange},
+    types::TokenType,
+};
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct FormatOptions {
+    pub tab_size: u32,
+    pub insert_spaces: bool,
+    pub max_blank_lines: usize,
+    pub normalize_strings: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            max_blank_lines: 1,
+            normalize_strings: true,
+        }
+    }
+}
+
+impl FormatOptions {
+    pub fn indent(&self, depth: usize) -> String {
+        match self.insert_spaces {
+            true => " ".repeat(self.tab_size as usize * depth),
+            false => "\t".repeat(depth),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct FormatError {
+    pub message: String,
+}
+
+/// A replacement of a range of the original source.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Edit {
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+/// Computes the edits that format `source`. Only the whitespace between
+/// tokens and the text of string literals is ever rewritten, so comments are
+/// kept and every edit can be applied on its own, e.g. for range formatting.
+pub fn format_edits(source: &str, options: &FormatOptions) -> Result<Vec<Edit>, FormatError> {
+    let parse = parse(source);
+    let scan_errors = scan_tokens(source.to_string()).errors;
+    if !parse.errors.is_empty() || !scan_errors.is_empty() {
+        return Err(FormatError {
+            message: "Cannot format a file with syntax errors".to_string(),
+        });
+    }
+
+    let root = parse.syntax();
+    let formatter = Formatter {
+        source,
+        options,
+        line_break: match source.contains("\r\n") {
+            true => "\r\n",
+            false => "\n",
+        },
+    };
+    Ok(formatter.edits(&root))
+}
+
+pub fn format_source(source: &str, options: &FormatOptions) -> Result<String, FormatError> {
+    let edits = format_edits(source, options)?;
+    Ok(apply_edits(source, &edits))
+}
+
+/// Applies non-overlapping edits to `source`.
+pub fn apply_edits(source: &str, edits: &[Edit]) -> String {
+    let mut sorted: Vec<&Edit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| edit.range.start);
+    let mut result = String::with_capacity(source.len());
+    let mut last_end = 0;
+    for edit in sorted {
+        result.push_str(&source[last_end..edit.range.start]);
+        result.push_str(&edit.new_text);
+        last_end = edit.range.end;
+    }
+    result.push_str(&source[last_end..]);
+    result
+}
+
+/// Indentation depth of a line whose first token starts at `offset`, worked
+/// out from the brackets before it so that it also works on incomplete code.
+/// Returns `None` when `offset` is inside a string or a comment.
+pub fn indent_depth(source: &str, offset: usize) -> Option<usize> {
+    let mut open: Vec<TokenType> = Vec::new();
+    let mut first = None;
+    for token in scan_tokens(source.to_string()).tokens {
+        if token.span.end.offset > offset && token.span.start.offset < offset {
+            return None;
+        }
+        if token.span.start.offset >= offset {
+            let before = &source[offset..token.span.start.offset];
+            if count_line_breaks(before) == 0 {
+                first = Some(token.token_type);
+            }
+            break;
+        }
+        close_bracket(&mut open, token.token_type);
+    }
+    if let Some(token_type @ (TokenType::RightBrace | TokenType::RightParen)) = first {
+        close_bracket(&mut open, token_type);
+    }
+    let braces = open
+        .iter()
+        .filter(|token_type| **token_type == TokenType::LeftBrace)
+        .count();
+    let continuation = usize::from(open.last() == Some(&TokenType::LeftParen));
+    Some(braces + continuation)
+}
+
+fn close_bracket(open: &mut Vec<TokenType>, token_type: TokenType) {
+    match token_type {
+        TokenType::LeftBrace | TokenType::LeftParen => open.push(token_type),
+        TokenType::RightBrace => {
+            while let Some(opening) = open.pop() {
+                if opening == TokenType::LeftBrace {
+                    break;
+                }
+            }
+        }
+        TokenType::RightParen if open.last() == Some(&TokenType::LeftParen) => {
+            open.pop();
+        }
+        _ => {}
+    }
+}
+
+/// Offset of the `{` matching the `}` that ends at `offset`.
+pub fn matching_left_brace(source: &str, offset: usize) -> Option<usize> {
+    let mut open = Vec::new();
+    for token in scan_tokens(source.to_string()).tokens {
+        match token.token_type {
+            TokenType::LeftBrace => open.push(token.span.start.offset),
+            TokenType::RightBrace if token.span.end.offset == offset => return open.pop(),
+            TokenType::RightBrace => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    options: &'a FormatOptions,
+    line_break: &'static str,
+}
+
+impl Formatter<'_> {
+    fn edits(&self, root: &SyntaxNode) -> Vec<Edit> {
+        let tokens: Vec<SyntaxToken> = root
+            .descendant_tokens()
+            .filter(|token| !matches!(token.kind(), TokenType::Whitespace | TokenType::Newline))
+            .collect();
+        let mut edits = Vec::new();
+        let mut depth: usize = 0;
+        let mut previous: Option<&SyntaxToken> = None;
+
+        for token in &tokens {
+            if token.kind() == TokenType::RightBrace {
+                depth = depth.saturating_sub(1);
+            }
+            let gap = TextRange::new(
+                previous.map_or(0, |previous| previous.text_range().end),
+                token.text_range().start,
+            );
+            let separator = match previous {
+                Some(previous) => self.separator(previous, token, gap, depth),
+                None => String::new(),
+            };
+            self.push_edit(&mut edits, gap, separator);
+
+            if token.kind() == TokenType::String && self.options.normalize_strings {
+                if let Some(normalized) = normalize_string(token.text()) {
+                    self.push_edit(&mut edits, token.text_range(), normalized);
+                }
+            }
+            if token.kind() == TokenType::LeftBrace {
+                depth += 1;
+            }
+            previous = Some(token);
+        }
+
+        let tail = TextRange::new(
+            previous.map_or(0, |previous| previous.text_range().end),
+            self.source.len(),
+        );
+        let final_line_break = match previous {
+            Some(_) => self.line_break.to_string(),
+            None => String::new(),
+        };
+        self.push_edit(&mut edits, tail, final_line_break);
+        edits
+    }
+
+    fn push_edit(&self, edits: &mut Vec<Edit>, range: TextRange, new_text: String) {
+        if self.source[range.start..range.end] != new_text {
+            edits.push(Edit { range, new_text });
+        }
+    }
+
+    fn separator(
+        &self,
+        previous: &SyntaxToken,
+        current: &SyntaxToken,
+        gap: TextRange,
+        depth: usize,
+    ) -> String {
+        let line_breaks = count_line_breaks(&self.source[gap.start..gap.end]);
+        let blank_lines = line_breaks
+            .saturating_sub(1)
+            .min(self.options.max_blank_lines);
+        let previous_kind = previous.kind();
+        let current_kind = current.kind();
+
+        if matches!(
+            previous_kind,
+            TokenType::LineComment | TokenType::DocComment
+        ) || (previous_kind == TokenType::BlockComment && line_breaks > 0)
+        {
+            return self.new_line(self.statement_blank_lines(previous, blank_lines), depth);
+        }
+        if current_kind.is_comment() {
+            return match line_breaks > 0 {
+                true => self.new_line(self.statement_blank_lines(previous, blank_lines), depth),
+                false => " ".to_string(),
+            };
+        }
+        if current_kind == TokenType::RightBrace {
+            return match previous_kind == TokenType::LeftBrace {
+                true => String::new(),
+                false => self.new_line(0, depth),
+            };
+        }
+        if previous_kind == TokenType::LeftBrace {
+            return self.new_line(0, depth);
+        }
+        if starts_statement(current) {
+            return self.new_line(self.statement_blank_lines(previous, blank_lines), depth);
+        }
+        if current_kind == TokenType::LeftBrace
+            || (previous_kind == TokenType::RightBrace
+                && matches!(current_kind, TokenType::Elif | TokenType::Else))
+        {
+            return " ".to_string();
+        }
+        if line_breaks > 0 {
+            let continuation = match current_kind == TokenType::RightParen {
+                true => 0,
+                false => 1,
+            };
+            return self.new_line(0, depth + continuation);
+        }
+        match needs_space(previous, current) {
+            true => " ".to_string(),
+            false => String::new(),
+        }
+    }
+
+    /// Declarations get a blank line around them, other statements keep up
+    /// to `max_blank_lines` of the original ones.
+    fn statement_blank_lines(&self, previous: &SyntaxToken, blank_lines: usize) -> usize {
+        let Some(statement) = statement_ending_at(previous) else {
+            return blank_lines;
+        };
+        let Some(next_statement) = statement.next_sibling() else {
+            return blank_lines;
+        };
+        match is_declaration(&statement) || is_declaration(&next_statement) {
+            true => blank_lines.max(1),
+            false => blank_lines,
+        }
+    }
+
+    fn new_line(&self, blank_lines: usize, depth: usize) -> String {
+        format!(
+            "{}{}",
+            self.line_break.repeat(blank_lines + 1),
+            self.options.indent(depth)
+        )
+    }
+}
+
+fn count_line_breaks(text: &str) -> usize {
+    text.matches('\n').count() + text.matches('\r').count() - text.matches("\r\n").count()
+}
+
+fn is_statement_level(node: &SyntaxNode) -> bool {
+    let statement_kind = matches!(
+        node.kind(),
+        NodeKind::VarDecl
+            | NodeKind::ClassDecl
+            | NodeKind::Method
+            | NodeKind::ExprStmt
+            | NodeKind::IfStmt
+            | NodeKind::WhileStmt
+            | NodeKind::ForStmt
+            | NodeKind::ReturnStmt
+            | NodeKind::Block
+    );
+    let parent_kind = node.parent().map(|parent| parent.kind());
+    statement_kind
+        && matches!(
+            parent_kind,
+            Some(NodeKind::SourceFile | NodeKind::Block | NodeKind::ClassDecl)
+        )
+}
+
+fn starts_statement(token: &SyntaxToken) -> bool {
+    let start = token.text_range().start;
+    token
+        .parent()
+        .ancestors()
+        .take_while(|node| node.text_range().start == start)
+        .any(|node| is_statement_level(&node))
+}
+
+fn statement_ending_at(token: &SyntaxToken) -> Option<SyntaxNode> {
+    let end = token.text_range().end;
+    token
+        .parent()
+        .ancestors()
+        .take_while(|node| node.text_range().end == end)
+        .find(is_statement_level)
+}
+
+fn is_declaration(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        NodeKind::ClassDecl | NodeKind::Method => true,
+        NodeKind::VarDecl => node.first_child_of(NodeKind::FunExpr).is_some(),
+        _ => false,
+    }
+}
+
+fn needs_space(previous: &SyntaxToken, current: &SyntaxToken) -> bool {
+    let previous_parent = previous.parent().kind();
+    let current_parent = current.parent().kind();
+    match (previous.kind(), current.kind()) {
+        (_, TokenType::RightParen | TokenType::Comma | TokenType::Semicolon | TokenType::Dot) => {
+            false
+        }
+        (TokenType::LeftParen | TokenType::Dot, _) => false,
+        (TokenType::Bang | TokenType::Minus, _) if previous_parent == NodeKind::PrefixExpr => false,
+        (_, TokenType::LeftParen) => {
+            !matches!(current_parent, NodeKind::ArgList | NodeKind::ParamList)
+        }
+        _ => true,
+    }
+}
+
+/// Rewrites a string literal with canonical escapes: `\u{...}` escapes of
+/// printable characters become the characters themselves and literal control
+/// characters other than line breaks become escapes.
+fn normalize_string(text: &str) -> Option<String> {
+    let body = &text[1..text.len() - 1];
+    let (_, errors) = unescape(body);
+    if !errors.is_empty() {
+        return None;
+    }
+
+    let mut result = String::from("\"");
+    let mut chars = body.char_indices();
+    while let Some((start, char)) = chars.next() {
+        if char != '\\' {
+            match char {
+                '\n' | '\r' => result.push(char),
+                _ => push_escaped(&mut result, char),
+            }
+            continue;
+        }
+        let (_, escape) = chars.next().unwrap();
+        if escape != 'u' {
+            result.push('\\');
+            result.push(escape);
+            continue;
+        }
+        let end = body[start..].find('}').unwrap() + start + 1;
+        while chars.offset() < end {
+            chars.next();
+        }
+        let (decoded, _) = unescape(&body[start..end]);
+        let decoded = decoded.chars().next().unwrap();
+        match decoded {
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            _ => push_escaped(&mut result, decoded),
+        }
+    }
+    result.push('"');
+
+    match result == text {
+        true => None,
+        false => Some(result),
+    }
+}
+
+fn push_escaped(result: &mut String, char: char) {
+    match char {
+        '"' => result.push_str("\\\""),
+        '\\' => result.push_str("\\\\"),
+        '\t' => result.push_str("\\t"),
+        '\x08' => result.push_str("\\b"),
+        '\x0c' => result.push_str("\\f"),
+        '\0' => result.push_str("\\0"),
+        char if char.is_control() => result.push_str(&format!("\\u{{{:x}}}", char as u32)),
+        char => result.push(char),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        format_source(source, &FormatOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn should_normalize_spacing_around_operators() {
+        let source = "severo   total=1+2 *(3-  -x)\nprint ( total ,!done )\n";
+        assert_eq!(
+            format(source),
+            "severo total = 1 + 2 * (3 - -x)\nprint(total, !done)\n"
+        );
+    }
+
+    #[test]
+    fn should_indent_blocks() {
+        let source =
+            "severo greet = fun(name){print(name) if name == \"\" { return } else {print(\"hi\")}}";
+        assert_eq!(
+            format(source),
+            r#"severo greet = fun(name) {
+    print(name)
+    if name == "" {
+        return
+    } else {
+        print("hi")
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn should_separate_declarations_with_blank_lines() {
+        let source = "severo a = 1\n\n\n\nprint(a)\nsevero f = fun() {}\nclass A { b() {} c() {} }\nprint(a)";
+        assert_eq!(
+            format(source),
+            "severo a = 1\n\nprint(a)\n\nsevero f = fun() {}\n\nclass A {\n    b() {}\n\n    c() {}\n}\n\nprint(a)\n"
+        );
+    }
+
+    #[test]
+    fn should_preserve_comments() {
+        let source = "// header\n\n\n/// doc\nsevero a = 1 // trailing\nwhile a {\n// inside\n  a = a - 1 /* inline */ }\n";
+        assert_eq!(
+            format(source),
+            "// header\n\n/// doc\nsevero a = 1 // trailing\nwhile a {\n    // inside\n    a = a - 1 /* inline */\n}\n"
+        );
+    }
+
+    #[test]
+    fn should_keep_line_breaks_inside_expressions() {
+        let source = "print(\na,\n        b\n    )";
+        assert_eq!(format(source), "print(\n    a,\n    b\n)\n");
+    }
+
+    #[test]
+    fn should_normalize_strings() {
+        let source = "print(\"\\u{48}i\\u{a}\tx\\u{22}\\\\\")";
+        assert_eq!(format(source), "print(\"Hi\\n\\tx\\\"\\\\\")\n");
+    }
+
+    #[test]
+    fn should_follow_the_indentation_options() {
+        let options = FormatOptions {
+            tab_size: 2,
+            insert_spaces: false,
+            normalize_strings: false,
+            ..Default::default()
+        };
+        let source = "while a { print(\"\\u{48}\") }";
+        assert_eq!(
+            format_source(source, &options).unwrap(),
+            "while a {\n\tprint(\"\\u{48}\")\n}\n"
+        );
+    }
+
+    #[test]
+    fn should_keep_crlf_line_breaks() {
+        assert_eq!(
+            format("print(1)\r\n\r\n\r\nprint(2)"),
+            "print(1)\r\n\r\nprint(2)\r\n"
+        );
+    }
+
+    #[test]
+    fn should_refuse_to_format_invalid_code() {
+        let result = format_edits("print(1", &FormatOptions::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_compute_indent_depth_of_incomplete_code() {
+        let source = "severo f = fun(a) {\n    print(\n\n    }\n\"{\n\"";
+        assert_eq!(indent_depth(source, 20), Some(1));
+        assert_eq!(indent_depth(source, 31), Some(2));
+        assert_eq!(indent_depth(source, 32), Some(0));
+        assert_eq!(indent_depth(source, 40), None);
+    }
+
+    #[test]
+    fn should_find_matching_left_brace() {
+        let source = "while a { if b { c() } }";
+        assert_eq!(matching_left_brace(source, 22), Some(15));
+        assert_eq!(matching_left_brace(source, 24), Some(8));
+        assert_eq!(matching_left_brace(source, 7), None);
+    }
+
+    #[test]
+    fn should_be_idempotent() {
+        let source = "class A<B{init(x){this.x=x}}\nfor(severo i=0;i<3;i=i+1){print(i)}";
+        let formatted = format(source);
+        assert_eq!(
+            formatted,
+            "class A < B {\n    init(x) {\n        this.x = x\n    }\n}\n\nfor (severo i = 0; i < 3; i = i + 1) {\n    print(i)\n}\n"
+        );
+        assert_eq!(format(&formatted), formatted);
+    }
+}
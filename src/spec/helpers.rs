@@ -1,3 +1,5 @@
+use super::types::EscapeError;
+
 pub fn rest_matches(start: usize, line: &str, next_chars: &str) -> bool {
     let rest_len = next_chars.len();
     match line.get(start..(start + rest_len)) {
@@ -6,24 +8,113 @@ pub fn rest_matches(start: usize, line: &str, next_chars: &str) -> bool {
     }
 }
 
-pub fn is_numeric(str: &str) -> bool {
-    str.chars().all(|c| c.is_ascii_digit())
+pub fn is_numeric(char: char) -> bool {
+    char.is_ascii_digit()
 }
 
-pub fn is_alpha(str: &str) -> bool {
-    str.chars().all(|c| c.is_ascii_alphabetic())
+pub fn is_alpha(char: char) -> bool {
+    char.is_ascii_alphabetic()
 }
 
-pub fn is_alphanumeric(str: &str) -> bool {
-    str.chars().all(|c| c.is_ascii_alphanumeric())
+pub fn is_alphanumeric(char: char) -> bool {
+    char.is_ascii_alphanumeric()
 }
 
-pub fn invert_escape(src: String) -> String {
-    src.replace("\\b", "\x08")
-        .replace("\\f", "\x0c")
-        .replace("\\n", "\n")
-        .replace("\\r", "\r")
-        .replace("\\t", "\t")
+/// Decodes the escape sequences of a string literal body. Invalid escapes are
+/// kept verbatim in the output and reported with their byte range in `src`.
+pub fn unescape(src: &str) -> (String, Vec<EscapeError>) {
+    let mut result = String::with_capacity(src.len());
+    let mut errors = Vec::new();
+    let mut chars = src.char_indices().peekable();
+
+    while let Some((start, char)) = chars.next() {
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+        let Some((escape_index, escape)) = chars.next() else {
+            errors.push(EscapeError {
+                message: "Incomplete escape sequence `\\`".to_string(),
+                start,
+                end: start + 1,
+            });
+            result.push('\\');
+            break;
+        };
+        let mut end = escape_index + escape.len_utf8();
+        let decoded = match escape {
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            'b' => Some('\x08'),
+            'f' => Some('\x0c'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            '0' => Some('\0'),
+            'u' => {
+                let mut digits = String::new();
+                let mut closed = false;
+                if let Some((brace_index, '{')) = chars.peek().copied() {
+                    chars.next();
+                    end = brace_index + 1;
+                    while let Some((index, next)) = chars.peek().copied() {
+                        if next == '}' {
+                            chars.next();
+                            end = index + 1;
+                            closed = true;
+                            break;
+                        }
+                        if !next.is_ascii_hexdigit() {
+                            break;
+                        }
+                        chars.next();
+                        end = index + 1;
+                        digits.push(next);
+                    }
+                }
+                match closed && !digits.is_empty() && digits.len() <= 6 {
+                    true => u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .and_then(char::from_u32),
+                    false => None,
+                }
+            }
+            _ => None,
+        };
+        match decoded {
+            Some(value) => result.push(value),
+            None => {
+                let sequence = &src[start..end];
+                let message = match escape {
+                    'u' => format!("Invalid unicode escape `{}`", sequence),
+                    _ => format!("Unknown escape sequence `{}`", sequence),
+                };
+                errors.push(EscapeError {
+                    message,
+                    start,
+                    end,
+                });
+                result.push_str(sequence);
+            }
+        }
+    }
+
+    (result, errors)
+}
+
+/// Levenshtein distance between `a` and `b`, counted in chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
 }
 
 #[cfg(test)]
@@ -47,4 +138,57 @@ mod tests {
         let line = "Hello, world!";
         assert!(!rest_matches(line.len(), line, "!"));
     }
+
+    #[test]
+    fn test_unescape_simple_escapes() {
+        let (result, errors) = unescape(r#"a\tb\n\"c\""#);
+        assert_eq!(result, "a\tb\n\"c\"");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_unescape_escaped_backslash_is_not_a_newline() {
+        let (result, errors) = unescape(r"\\n");
+        assert_eq!(result, "\\n");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_unescape_unicode() {
+        let (result, errors) = unescape(r"\u{48}i \u{1F600}");
+        assert_eq!(result, "Hi \u{1F600}");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_unescape_reports_unknown_escape() {
+        let (result, errors) = unescape(r"ab\qc");
+        assert_eq!(result, r"ab\qc");
+        assert_eq!(
+            errors,
+            vec![EscapeError {
+                message: "Unknown escape sequence `\\q`".to_string(),
+                start: 2,
+                end: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unescape_reports_invalid_unicode() {
+        let (_, errors) = unescape(r"\u{D800} \u{zz}");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "Invalid unicode escape `\\u{D800}`");
+        assert_eq!((errors[0].start, errors[0].end), (0, 8));
+        assert_eq!(errors[1].message, "Invalid unicode escape `\\u{`");
+        assert_eq!((errors[1].start, errors[1].end), (9, 12));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("variableNam", "variableName"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
 }
@@ -0,0 +1,584 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{
+    ast::{AssignExpr, AstNode, Block, ClassDecl, Expr, ParamList, Stmt, VarDecl},
+    resolver::{Resolution, SymbolId, SymbolKind},
+    syntax::{NodeKind, SyntaxNode, TextRange},
+    types::TokenType,
+};
+
+/// What the value of an expression is known to be.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Type {
+    Number,
+    String,
+    Bool,
+    Nil,
+    Function {
+        params: Vec<String>,
+        returns: Box<Type>,
+    },
+    /// A class, with the parameters of its `init` when they are known.
+    Class {
+        name: String,
+        params: Option<Vec<String>>,
+    },
+    Instance(String),
+    Unknown,
+}
+
+impl Type {
+    /// The type of a value that may come from either `self` or `other`.
+    pub fn join(self, other: Type) -> Type {
+        match self == other {
+            true => self,
+            false => Type::Unknown,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        *self != Type::Unknown
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Number => write!(f, "number"),
+            Type::String => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Nil => write!(f, "nil"),
+            Type::Function { params, returns } => match returns.is_known() {
+                true => write!(f, "fun({}) -> {}", params.join(", "), returns),
+                false => write!(f, "fun({})", params.join(", ")),
+            },
+            Type::Class { name, .. } => write!(f, "class {}", name),
+            Type::Instance(name) => write!(f, "{}", name),
+            Type::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// An operation on values of types it cannot work with.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Mismatch {
+    pub range: TextRange,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct ClassInfo {
+    superclass: Option<String>,
+    methods: HashMap<String, Type>,
+}
+
+/// The types of the expressions and symbols of a file, and the operations
+/// that would fail at runtime because of them.
+#[derive(Default)]
+pub struct Inference {
+    expressions: HashMap<TextRange, Type>,
+    symbols: HashMap<SymbolId, Type>,
+    classes: HashMap<String, ClassInfo>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Inference {
+    pub fn infer(root: &SyntaxNode, resolution: &Resolution) -> Inference {
+        let mut inference = Inference::default();
+        for node in root.descendants() {
+            match node.kind() {
+                NodeKind::VarDecl => inference.declare_variable(root, &node, resolution),
+                NodeKind::ClassDecl => inference.declare_class(&node, resolution),
+                kind if Expr::can_cast(kind) => {
+                    inference.expr(&node, resolution);
+                }
+                _ => {}
+            }
+        }
+        inference
+            .mismatches
+            .sort_by_key(|mismatch| mismatch.range.start);
+        inference
+    }
+
+    pub fn type_of(&self, range: TextRange) -> &Type {
+        self.expressions.get(&range).unwrap_or(&Type::Unknown)
+    }
+
+    pub fn symbol_type(&self, symbol: SymbolId) -> &Type {
+        self.symbols.get(&symbol).unwrap_or(&Type::Unknown)
+    }
+
+    /// Variables take the type of their initializer, joined with the one of
+    /// every value later assigned to them.
+    fn declare_variable(&mut self, root: &SyntaxNode, node: &SyntaxNode, resolution: &Resolution) {
+        let Some(declaration) = VarDecl::cast(node.clone()) else {
+            return;
+        };
+        let mut value = match declaration.initializer() {
+            Some(initializer) => self.expr(initializer.syntax(), resolution),
+            None => Type::Nil,
+        };
+        let Some(symbol) = declared_symbol(resolution, node) else {
+            return;
+        };
+        let assignments: Vec<AssignExpr> = resolution
+            .references_to(symbol)
+            .filter(|reference| reference.is_write)
+            .filter_map(|reference| {
+                root.covering_element(reference.range)
+                    .parent()?
+                    .ancestors()
+                    .find_map(AssignExpr::cast)
+            })
+            .collect();
+        // Assigned values may read the variable, e.g. `total = total + i`,
+        // so they are checked against the type joined from all of them. It
+        // is the initializer's type when they all agree with it, found by
+        // typing them once without keeping the results.
+        self.symbols.insert(symbol, value.clone());
+        let expressions = self.expressions.clone();
+        let mismatches = self.mismatches.len();
+        let agree = assignments
+            .iter()
+            .all(|assignment| self.assigned_type(assignment, resolution) == value);
+        self.expressions = expressions;
+        self.mismatches.truncate(mismatches);
+        if !agree {
+            self.symbols.insert(symbol, Type::Unknown);
+        }
+        for assignment in assignments {
+            let assigned = self.assigned_type(&assignment, resolution);
+            value = value.join(assigned);
+        }
+        self.symbols.insert(symbol, value);
+    }
+
+    fn assigned_type(&mut self, assignment: &AssignExpr, resolution: &Resolution) -> Type {
+        match assignment.value() {
+            Some(assigned) => self.expr(assigned.syntax(), resolution),
+            None => Type::Unknown,
+        }
+    }
+
+    fn declare_class(&mut self, node: &SyntaxNode, resolution: &Resolution) {
+        let Some(class) = ClassDecl::cast(node.clone()) else {
+            return;
+        };
+        let Some(name) = class.name().map(|name| name.text()) else {
+            return;
+        };
+        let superclass = class.superclass().map(|superclass| superclass.text());
+        self.classes.insert(
+            name.clone(),
+            ClassInfo {
+                superclass: superclass.clone(),
+                methods: HashMap::new(),
+            },
+        );
+        for method in class.methods() {
+            let Some(method_name) = method.name().map(|name| name.text()) else {
+                continue;
+            };
+            let mut method_type = self.function(method.param_list(), method.body(), resolution);
+            if let Type::Function { returns, .. } = &mut method_type {
+                if method_name == "init" {
+                    **returns = Type::Instance(name.clone());
+                }
+            }
+            if let Some(info) = self.classes.get_mut(&name) {
+                info.methods.insert(method_name, method_type);
+            }
+        }
+
+        let params = match self.method(&name, "init") {
+            Some(Type::Function { params, .. }) => Some(params),
+            _ if superclass.is_some_and(|superclass| !self.classes.contains_key(&superclass)) => {
+                None
+            }
+            _ => Some(Vec::new()),
+        };
+        if let Some(symbol) = declared_symbol(resolution, node) {
+            self.symbols.insert(symbol, Type::Class { name, params });
+        }
+    }
+
+    /// The type of a method of `class`, looked up through its superclasses.
+    fn method(&self, class: &str, name: &str) -> Option<Type> {
+        let mut current = Some(class.to_string());
+        let mut depth = 0;
+        while let Some(class) = current.filter(|_| depth < self.classes.len()) {
+            let info = self.classes.get(&class)?;
+            if let Some(method) = info.methods.get(name) {
+                return Some(method.clone());
+            }
+            current = info.superclass.clone();
+            depth += 1;
+        }
+        None
+    }
+
+    /// A function returns the join of its `return` values, and `nil` when it
+    /// can also run off the end of its body.
+    fn function(
+        &mut self,
+        params: Option<ParamList>,
+        body: Option<Block>,
+        resolution: &Resolution,
+    ) -> Type {
+        let params = params
+            .map(|params| params.params().map(|param| param.text()).collect())
+            .unwrap_or_default();
+        let mut returns: Option<Type> = None;
+        if let Some(body) = &body {
+            for statement in function_returns(body.syntax()) {
+                let value = match statement.children().find_map(Expr::cast) {
+                    Some(value) => self.expr(value.syntax(), resolution),
+                    None => Type::Nil,
+                };
+                returns = Some(match returns {
+                    Some(returns) => returns.join(value),
+                    None => value,
+                });
+            }
+        }
+        let ends_with_return = body
+            .and_then(|body| body.statements().last())
+            .is_some_and(|statement| matches!(statement, Stmt::ReturnStmt(_)));
+        let returns = match (returns, ends_with_return) {
+            (Some(returns), true) => returns,
+            (Some(returns), false) => returns.join(Type::Nil),
+            (None, _) => Type::Nil,
+        };
+        Type::Function {
+            params,
+            returns: Box::new(returns),
+        }
+    }
+
+    fn expr(&mut self, node: &SyntaxNode, resolution: &Resolution) -> Type {
+        if let Some(known) = self.expressions.get(&node.text_range()) {
+            return known.clone();
+        }
+        let result = match Expr::cast(node.clone()) {
+            Some(Expr::Literal(literal)) => match literal.token().map(|token| token.kind()) {
+                Some(TokenType::Number) => Type::Number,
+                Some(TokenType::String) => Type::String,
+                Some(TokenType::True | TokenType::False) => Type::Bool,
+                Some(TokenType::Nil) => Type::Nil,
+                _ => Type::Unknown,
+            },
+            Some(Expr::NameRef(name_ref)) => resolution
+                .references
+                .iter()
+                .find(|reference| reference.range == name_ref.text_range())
+                .and_then(|reference| reference.symbol)
+                .map_or(Type::Unknown, |symbol| self.symbol_type(symbol).clone()),
+            Some(Expr::ParenExpr(paren)) => match paren.expr() {
+                Some(inner) => self.expr(inner.syntax(), resolution),
+                None => Type::Unknown,
+            },
+            Some(Expr::PrefixExpr(prefix)) => {
+                let operand = prefix.expr().map(|operand| {
+                    let operand_type = self.expr(operand.syntax(), resolution);
+                    (operand.text_range(), operand_type)
+                });
+                match prefix.op_token().map(|token| token.kind()) {
+                    Some(TokenType::Bang) => Type::Bool,
+                    Some(TokenType::Minus) => {
+                        if let Some((range, operand)) = operand {
+                            self.expect_number(range, &operand);
+                        }
+                        Type::Number
+                    }
+                    _ => Type::Unknown,
+                }
+            }
+            Some(Expr::BinaryExpr(binary)) => {
+                let mut operand = |expr: Option<Expr>| match expr {
+                    Some(expr) => (expr.text_range(), self.expr(expr.syntax(), resolution)),
+                    None => (TextRange::default(), Type::Unknown),
+                };
+                let (lhs_range, lhs) = operand(binary.lhs());
+                let (rhs_range, rhs) = operand(binary.rhs());
+                match binary.op_token().map(|token| token.kind()) {
+                    Some(TokenType::Plus) => match (&lhs, &rhs) {
+                        (Type::Number, Type::Number) => Type::Number,
+                        (Type::String, Type::String) => Type::String,
+                        (Type::Number | Type::String, Type::Number | Type::String) => {
+                            self.mismatch(
+                                binary.text_range(),
+                                format!("Cannot add {} and {}", lhs, rhs),
+                            );
+                            Type::Unknown
+                        }
+                        _ => {
+                            self.expect_addable(lhs_range, &lhs);
+                            self.expect_addable(rhs_range, &rhs);
+                            Type::Unknown
+                        }
+                    },
+                    Some(TokenType::Minus | TokenType::Star | TokenType::Slash) => {
+                        self.expect_number(lhs_range, &lhs);
+                        self.expect_number(rhs_range, &rhs);
+                        Type::Number
+                    }
+                    Some(
+                        TokenType::Less
+                        | TokenType::LessEqual
+                        | TokenType::Greater
+                        | TokenType::GreaterEqual,
+                    ) => {
+                        self.expect_number(lhs_range, &lhs);
+                        self.expect_number(rhs_range, &rhs);
+                        Type::Bool
+                    }
+                    Some(TokenType::EqualEqual | TokenType::BangEqual) => Type::Bool,
+                    Some(TokenType::And | TokenType::Or) => lhs.join(rhs),
+                    _ => Type::Unknown,
+                }
+            }
+            Some(Expr::AssignExpr(assign)) => match assign.value() {
+                Some(value) => self.expr(value.syntax(), resolution),
+                None => Type::Unknown,
+            },
+            Some(Expr::CallExpr(call)) => {
+                let callee = call
+                    .callee()
+                    .map(|callee| self.expr(callee.syntax(), resolution));
+                let args = call.arg_list().into_iter().flat_map(|args| args.args());
+                let arity = args.map(|arg| self.expr(arg.syntax(), resolution)).count();
+                let (params, returns) = match callee.unwrap_or(Type::Unknown) {
+                    Type::Function { params, returns } => (Some(params), *returns),
+                    Type::Class { name, params } => (params, Type::Instance(name)),
+                    Type::Unknown => (None, Type::Unknown),
+                    other => {
+                        self.mismatch(
+                            call.text_range(),
+                            format!("Cannot call a value of type {}", other),
+                        );
+                        (None, Type::Unknown)
+                    }
+                };
+                if let Some(params) = params.filter(|params| params.len() != arity) {
+                    self.mismatch(
+                        call.text_range(),
+                        format!(
+                            "Expected {} argument{}, found {}",
+                            params.len(),
+                            if params.len() == 1 { "" } else { "s" },
+                            arity
+                        ),
+                    );
+                }
+                returns
+            }
+            Some(Expr::FieldExpr(field)) => {
+                let receiver = field
+                    .receiver()
+                    .map(|receiver| self.expr(receiver.syntax(), resolution));
+                let name = field.name_ref().map(|name| name.text());
+                match (receiver, name) {
+                    (Some(Type::Instance(class)), Some(name)) => {
+                        self.method(&class, &name).unwrap_or(Type::Unknown)
+                    }
+                    _ => Type::Unknown,
+                }
+            }
+            Some(Expr::FunExpr(function)) => {
+                self.function(function.param_list(), function.body(), resolution)
+            }
+            Some(Expr::ThisExpr(_)) => node
+                .ancestors()
+                .find_map(ClassDecl::cast)
+                .and_then(|class| class.name())
+                .map_or(Type::Unknown, |name| Type::Instance(name.text())),
+            _ => Type::Unknown,
+        };
+        self.expressions.insert(node.text_range(), result.clone());
+        result
+    }
+
+    fn expect_number(&mut self, range: TextRange, found: &Type) {
+        if found.is_known() && *found != Type::Number {
+            self.mismatch(range, format!("Expected a number, found {}", found));
+        }
+    }
+
+    fn expect_addable(&mut self, range: TextRange, found: &Type) {
+        if found.is_known() && !matches!(found, Type::Number | Type::String) {
+            self.mismatch(
+                range,
+                format!("Expected a number or a string, found {}", found),
+            );
+        }
+    }
+
+    fn mismatch(&mut self, range: TextRange, message: String) {
+        self.mismatches.push(Mismatch { range, message });
+    }
+}
+
+fn declared_symbol(resolution: &Resolution, declaration: &SyntaxNode) -> Option<SymbolId> {
+    resolution.symbols.iter().position(|symbol| {
+        symbol.declaration_range == declaration.text_range()
+            && matches!(
+                symbol.kind,
+                SymbolKind::Variable | SymbolKind::Function | SymbolKind::Class
+            )
+    })
+}
+
+/// The `return` statements of a function body, leaving out nested functions.
+fn function_returns(body: &SyntaxNode) -> Vec<SyntaxNode> {
+    let mut returns = Vec::new();
+    for child in body.children() {
+        match child.kind() {
+            NodeKind::ReturnStmt => returns.push(child),
+            NodeKind::FunExpr | NodeKind::ClassDecl => {}
+            _ => returns.extend(function_returns(&child)),
+        }
+    }
+    returns
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::parser::parse;
+
+    use super::*;
+
+    fn infer(source: &str) -> (Resolution, Inference) {
+        let root = parse(source).syntax();
+        let resolution = Resolution::resolve(&root);
+        let inference = Inference::infer(&root, &resolution);
+        (resolution, inference)
+    }
+
+    fn symbol_types(source: &str) -> Vec<(String, String)> {
+        let (resolution, inference) = infer(source);
+        resolution
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| symbol.kind != SymbolKind::Parameter)
+            .map(|(id, symbol)| (symbol.name.clone(), inference.symbol_type(id).to_string()))
+            .collect()
+    }
+
+    fn mismatches(source: &str) -> Vec<(&str, String)> {
+        infer(source)
+            .1
+            .mismatches
+            .into_iter()
+            .map(|mismatch| {
+                let range = mismatch.range;
+                (&source[range.start..range.end], mismatch.message)
+            })
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(name, ty)| (name.to_string(), ty.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn should_infer_literals_and_operators() {
+        let source = concat!(
+            "severo a = 10\n",
+            "severo b = \"x\" + \"y\"\n",
+            "severo c = a < 3 or !a\n",
+            "severo d\n",
+            "severo e = -(a * 2)\n",
+            "severo f = a + b\n",
+        );
+        assert_eq!(
+            symbol_types(source),
+            pairs(&[
+                ("a", "number"),
+                ("b", "string"),
+                ("c", "bool"),
+                ("d", "nil"),
+                ("e", "number"),
+                ("f", "unknown"),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_infer_functions_and_classes() {
+        let source = concat!(
+            "class Point {\n",
+            "    init(x, y) {}\n",
+            "    norm() { return 1 }\n",
+            "}\n",
+            "class Point3 < Point {}\n",
+            "severo p = Point3(1, 2)\n",
+            "severo n = p.norm()\n",
+            "severo add = fun(a, b) { return a + b }\n",
+            "severo half = fun(a) { return a / 2 }\n",
+            "severo maybe = fun(a) { if a { return 1 } }\n",
+        );
+        assert_eq!(
+            symbol_types(source),
+            pairs(&[
+                ("Point", "class Point"),
+                ("Point3", "class Point3"),
+                ("p", "Point3"),
+                ("n", "number"),
+                ("add", "fun(a, b)"),
+                ("half", "fun(a) -> number"),
+                ("maybe", "fun(a)"),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_propagate_through_assignments() {
+        let source = concat!(
+            "severo count = 0\n",
+            "count = count + 1\n",
+            "severo name = \"a\"\n",
+            "name = nil\n",
+        );
+        assert_eq!(
+            symbol_types(source),
+            pairs(&[("count", "number"), ("name", "unknown")])
+        );
+    }
+
+    #[test]
+    fn should_report_mismatches() {
+        let source = concat!(
+            "print(\"a\" - 1)\n",
+            "print(1 + \"a\")\n",
+            "print(-true < 2)\n",
+            "severo f = fun(a) {}\n",
+            "f(1, 2)\n",
+            "severo n = 3\n",
+            "n()\n",
+            "print(unknown - 1)\n",
+            "severo x = nil\n",
+            "x = 0\n",
+            "x = x + 1\n",
+            "severo total = nil\n",
+            "for (severo i = 0; i < 3; i = i + 1) {\n",
+            "    total = total + i\n",
+            "}\n",
+        );
+        assert_eq!(
+            mismatches(source),
+            vec![
+                ("\"a\"", "Expected a number, found string".to_string()),
+                ("1 + \"a\"", "Cannot add number and string".to_string()),
+                ("true", "Expected a number, found bool".to_string()),
+                ("f(1, 2)", "Expected 1 argument, found 2".to_string()),
+                ("n()", "Cannot call a value of type number".to_string()),
+            ]
+        );
+    }
+}
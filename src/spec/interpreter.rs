@@ -0,0 +1,1114 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::{
+    ast::{
+        AstNode, BinaryExpr, Block, CallExpr, ClassDecl, Expr, FieldExpr, ForStmt, FunExpr, IfStmt,
+        ParamList, SourceFile, Stmt, SuperExpr, VarDecl, WhileStmt,
+    },
+    syntax::TextRange,
+    types::{Literal as LiteralValue, TokenType},
+};
+
+/// Calls nested deeper than this stop the program with an error.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// Calls, blocks and expressions nest as deep as the program does, and each
+/// level takes a few Rust frames, so the stack grows by `STACK_GROWTH` once
+/// less than `STACK_RED_ZONE` is left.
+const STACK_RED_ZONE: usize = 1024 * 1024;
+const STACK_GROWTH: usize = 8 * 1024 * 1024;
+
+#[derive(Clone)]
+pub enum Value {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Nil,
+    Builtin(&'static str),
+    Function(Rc<Function>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    /// The name of the value's type, as the inferred types are shown.
+    pub fn type_name(&self) -> String {
+        match self {
+            Value::Number(_) => "number".to_string(),
+            Value::String(_) => "string".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Nil => "nil".to_string(),
+            Value::Builtin(_) | Value::Function(_) => "function".to_string(),
+            Value::Class(class) => format!("class {}", class.name),
+            Value::Instance(instance) => instance.borrow().class.name.clone(),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Builtin(a), Value::Builtin(b)) => a == b,
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
+            (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::String(string) => write!(f, "{}", string),
+            Value::Bool(boolean) => write!(f, "{}", boolean),
+            Value::Nil => write!(f, "nil"),
+            Value::Builtin(name) => write!(f, "<fun {}>", name),
+            Value::Function(function) => write!(f, "<fun {}>", function.name),
+            Value::Class(class) => write!(f, "{}", class.name),
+            Value::Instance(instance) => write!(f, "{} instance", instance.borrow().class.name),
+        }
+    }
+}
+
+/// Closures and environments point at each other, so values are debugged
+/// through their printed form.
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(string) => write!(f, "{:?}", string),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+pub struct Function {
+    name: String,
+    params: Vec<String>,
+    body: Option<Block>,
+    closure: Environment,
+    is_initializer: bool,
+}
+
+impl Function {
+    fn new(
+        name: String,
+        params: Option<ParamList>,
+        body: Option<Block>,
+        closure: Environment,
+    ) -> Self {
+        Function {
+            is_initializer: false,
+            name,
+            params: params
+                .map(|params| params.params().map(|param| param.text()).collect())
+                .unwrap_or_default(),
+            body,
+            closure,
+        }
+    }
+
+    /// The method with `this` bound to `instance` in `closure`, a child of
+    /// the method's own.
+    fn bind(&self, closure: Environment, instance: Value) -> Rc<Function> {
+        closure.define("this", instance);
+        Rc::new(Function {
+            name: self.name.clone(),
+            params: self.params.clone(),
+            body: self.body.clone(),
+            closure,
+            is_initializer: self.is_initializer,
+        })
+    }
+}
+
+pub struct Class {
+    name: String,
+    superclass: Option<Rc<Class>>,
+    methods: HashMap<String, Rc<Function>>,
+}
+
+impl Class {
+    fn find_method(&self, name: &str) -> Option<Rc<Function>> {
+        match self.methods.get(name) {
+            Some(method) => Some(method.clone()),
+            None => self.superclass.as_ref()?.find_method(name),
+        }
+    }
+}
+
+pub struct Instance {
+    class: Rc<Class>,
+    fields: HashMap<String, Value>,
+}
+
+impl Instance {
+    /// The fields of the instance, sorted by name.
+    pub fn fields(&self) -> Vec<(String, Value)> {
+        let mut fields: Vec<(String, Value)> = self
+            .fields
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+        fields
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        drop_values(std::mem::take(&mut self.fields).into_values().collect());
+    }
+}
+
+struct Scope {
+    values: Vec<(String, Value)>,
+    parent: Option<Environment>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let values = std::mem::take(&mut self.values);
+        drop_values(values.into_iter().map(|(_, value)| value).collect());
+    }
+}
+
+/// Drops `values` one by one, with the instances and scopes only they hold,
+/// as dropping e.g. a long linked list of instances recursively would
+/// overflow the stack.
+fn drop_values(mut values: Vec<Value>) {
+    let mut scopes: Vec<Environment> = Vec::new();
+    loop {
+        if let Some(value) = values.pop() {
+            match value {
+                Value::Instance(instance) => {
+                    if let Some(instance) = Rc::into_inner(instance) {
+                        let fields = std::mem::take(&mut instance.into_inner().fields);
+                        values.extend(fields.into_values());
+                    }
+                }
+                Value::Function(function) => {
+                    if let Some(function) = Rc::into_inner(function) {
+                        scopes.push(function.closure);
+                    }
+                }
+                _ => {}
+            }
+        } else if let Some(env) = scopes.pop() {
+            if let Some(scope) = Rc::into_inner(env.0) {
+                let mut scope = scope.into_inner();
+                let bindings = std::mem::take(&mut scope.values);
+                values.extend(bindings.into_iter().map(|(_, value)| value));
+                scopes.extend(scope.parent.take());
+            }
+        } else {
+            break;
+        }
+    }
+}
+
+/// The bindings of a scope, shared with the closures created in it.
+#[derive(Clone)]
+pub struct Environment(Rc<RefCell<Scope>>);
+
+impl Environment {
+    fn new(parent: Option<Environment>) -> Self {
+        Environment(Rc::new(RefCell::new(Scope {
+            values: Vec::new(),
+            parent,
+        })))
+    }
+
+    fn child(&self) -> Environment {
+        Environment::new(Some(self.clone()))
+    }
+
+    pub fn parent(&self) -> Option<Environment> {
+        self.0.borrow().parent.clone()
+    }
+
+    /// The bindings declared in this scope, in the order they were declared.
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        self.0.borrow().values.clone()
+    }
+
+    /// Drops the bindings of this scope.
+    fn clear(&self) {
+        let values = std::mem::take(&mut self.0.borrow_mut().values);
+        drop(values);
+    }
+
+    pub fn define(&self, name: &str, value: Value) {
+        let mut scope = self.0.borrow_mut();
+        match scope.values.iter_mut().find(|(other, _)| other == name) {
+            Some((_, old)) => *old = value,
+            None => scope.values.push((name.to_string(), value)),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        let scope = self.0.borrow();
+        match scope.values.iter().find(|(other, _)| other == name) {
+            Some((_, value)) => Some(value.clone()),
+            None => scope.parent.as_ref()?.get(name),
+        }
+    }
+
+    /// Replaces the value of the nearest binding of `name`, returning whether
+    /// there was one.
+    fn assign(&self, name: &str, value: Value) -> bool {
+        let mut scope = self.0.borrow_mut();
+        match scope.values.iter_mut().find(|(other, _)| other == name) {
+            Some((_, old)) => {
+                *old = value;
+                true
+            }
+            None => scope
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.assign(name, value)),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct RuntimeError {
+    pub message: String,
+    /// The range of the expression that failed.
+    pub range: TextRange,
+}
+
+fn error<T>(range: TextRange, message: String) -> Result<T, RuntimeError> {
+    Err(RuntimeError { message, range })
+}
+
+type Printer = Box<dyn FnMut(&str)>;
+
+/// A call in progress, as a debugger shows it.
+#[derive(Clone)]
+pub struct Frame {
+    pub name: String,
+    /// The range of the statement the call is running.
+    pub range: TextRange,
+    /// The innermost scope of that statement.
+    pub env: Environment,
+}
+
+/// Watches a program run, e.g. to pause it at breakpoints.
+pub trait Debugger {
+    /// Called before every statement, with the outermost frame first.
+    fn statement(&mut self, frames: &[Frame]);
+}
+
+/// What running a statement led to: `Some` when a `return` ran.
+type Exec = Result<Option<Value>, RuntimeError>;
+
+/// Runs Severo code, writing what it prints to `output` unless a printer
+/// is given.
+pub struct Interpreter {
+    globals: Environment,
+    pub output: String,
+    printer: Option<Printer>,
+    /// Set from another thread to stop the program.
+    stop: Option<Arc<AtomicBool>>,
+    debugger: Option<Box<dyn Debugger>>,
+    frames: Vec<Frame>,
+    /// The scopes the program created, cleared when the interpreter is
+    /// dropped.
+    environments: Vec<Weak<RefCell<Scope>>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let globals = Environment::new(None);
+        globals.define("print", Value::Builtin("print"));
+        Interpreter {
+            globals,
+            output: String::new(),
+            printer: None,
+            stop: None,
+            debugger: None,
+            frames: Vec::new(),
+            environments: Vec::new(),
+        }
+    }
+
+    /// Sends every printed line to `printer` instead of `output`.
+    pub fn with_printer(mut self, printer: impl FnMut(&str) + 'static) -> Self {
+        self.printer = Some(Box::new(printer));
+        self
+    }
+
+    /// Stops the program with an error once `stop` is set.
+    pub fn with_stop(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn with_debugger(mut self, debugger: impl Debugger + 'static) -> Self {
+        self.debugger = Some(Box::new(debugger));
+        self
+    }
+
+    /// Runs the statements of `file`. Its declarations stay in the globals,
+    /// so later runs can use them.
+    pub fn run(&mut self, file: &SourceFile) -> Result<(), RuntimeError> {
+        let globals = self.globals.clone();
+        self.frames.push(Frame {
+            name: "main".to_string(),
+            range: file.text_range(),
+            env: globals.clone(),
+        });
+        let result = self.block_statements(file.statements(), &globals);
+        self.frames.pop();
+        result.map(|_| ())
+    }
+
+    pub fn globals(&self) -> Environment {
+        self.globals.clone()
+    }
+
+    /// Evaluates `expr` in `env`, e.g. the scope of a paused frame.
+    pub fn evaluate(&mut self, expr: &Expr, env: &Environment) -> Result<Value, RuntimeError> {
+        self.expr(expr, env)
+    }
+
+    /// A scope inside `parent`.
+    fn child(&mut self, parent: &Environment) -> Environment {
+        let env = parent.child();
+        if self.environments.len() == self.environments.capacity() {
+            self.environments.retain(|env| env.strong_count() > 0);
+            self.environments.reserve(self.environments.len());
+        }
+        self.environments.push(Rc::downgrade(&env.0));
+        env
+    }
+
+    fn check_stop(&self, range: TextRange) -> Result<(), RuntimeError> {
+        match &self.stop {
+            Some(stop) if stop.load(Ordering::Relaxed) => {
+                error(range, "The program was stopped".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt, env: &Environment) -> Exec {
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+            self.nested_statement(statement, env)
+        })
+    }
+
+    fn nested_statement(&mut self, statement: &Stmt, env: &Environment) -> Exec {
+        self.check_stop(statement.text_range())?;
+        if let Some(frame) = self.frames.last_mut() {
+            frame.range = statement.text_range();
+            frame.env = env.clone();
+        }
+        if let Some(debugger) = &mut self.debugger {
+            debugger.statement(&self.frames);
+        }
+        match statement {
+            Stmt::VarDecl(declaration) => self.var_decl(declaration, env)?,
+            Stmt::ClassDecl(class) => self.class_decl(class, env)?,
+            Stmt::ExprStmt(statement) => {
+                self.required(statement.expr(), statement.text_range(), env)?;
+            }
+            Stmt::IfStmt(statement) => return self.if_stmt(statement, env),
+            Stmt::WhileStmt(statement) => return self.while_stmt(statement, env),
+            Stmt::ForStmt(statement) => return self.for_stmt(statement, env),
+            Stmt::ReturnStmt(statement) => {
+                let value = match statement.value() {
+                    Some(value) => self.expr(&value, env)?,
+                    None => Value::Nil,
+                };
+                return Ok(Some(value));
+            }
+            Stmt::Block(block) => {
+                let env = self.child(env);
+                return self.block(Some(block), env);
+            }
+        }
+        Ok(None)
+    }
+
+    fn block(&mut self, block: Option<&Block>, env: Environment) -> Exec {
+        self.block_statements(block.into_iter().flat_map(|block| block.statements()), &env)
+    }
+
+    fn block_statements(
+        &mut self,
+        statements: impl Iterator<Item = Stmt>,
+        env: &Environment,
+    ) -> Exec {
+        for statement in statements {
+            if let Some(value) = self.statement(&statement, env)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    fn var_decl(&mut self, declaration: &VarDecl, env: &Environment) -> Result<(), RuntimeError> {
+        let value = match declaration.initializer() {
+            Some(initializer) => self.expr(&initializer, env)?,
+            None => Value::Nil,
+        };
+        if let Some(name) = declaration.name() {
+            env.define(&name.text(), value);
+        }
+        Ok(())
+    }
+
+    fn class_decl(&mut self, class: &ClassDecl, env: &Environment) -> Result<(), RuntimeError> {
+        let Some(name) = class.name().map(|name| name.text()) else {
+            return Ok(());
+        };
+        let superclass = match class.superclass() {
+            Some(superclass) => match env.get(&superclass.text()) {
+                Some(Value::Class(superclass)) => Some(superclass),
+                Some(other) => {
+                    return error(
+                        superclass.text_range(),
+                        format!("Expected a class, found {}", other.type_name()),
+                    )
+                }
+                None => {
+                    return error(
+                        superclass.text_range(),
+                        format!("Undefined variable `{}`", superclass.text()),
+                    )
+                }
+            },
+            None => None,
+        };
+        let closure = match &superclass {
+            Some(superclass) => {
+                let closure = self.child(env);
+                closure.define("super", Value::Class(superclass.clone()));
+                closure
+            }
+            None => env.clone(),
+        };
+        let methods = class
+            .methods()
+            .filter_map(|method| {
+                let method_name = method.name()?.text();
+                let mut function = Function::new(
+                    method_name.clone(),
+                    method.param_list(),
+                    method.body(),
+                    closure.clone(),
+                );
+                function.is_initializer = method_name == "init";
+                Some((method_name, Rc::new(function)))
+            })
+            .collect();
+        let class = Class {
+            name: name.clone(),
+            superclass,
+            methods,
+        };
+        env.define(&name, Value::Class(Rc::new(class)));
+        Ok(())
+    }
+
+    fn if_stmt(&mut self, statement: &IfStmt, env: &Environment) -> Exec {
+        let condition = self.required(statement.condition(), statement.text_range(), env)?;
+        if condition.is_truthy() {
+            let env = self.child(env);
+            return self.block(statement.then_branch().as_ref(), env);
+        }
+        for branch in statement.elif_branches() {
+            let condition = self.required(branch.condition(), branch.text_range(), env)?;
+            if condition.is_truthy() {
+                let env = self.child(env);
+                return self.block(branch.block().as_ref(), env);
+            }
+        }
+        match statement.else_branch() {
+            Some(branch) => {
+                let env = self.child(env);
+                self.block(branch.block().as_ref(), env)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn while_stmt(&mut self, statement: &WhileStmt, env: &Environment) -> Exec {
+        let body = statement.body();
+        while self
+            .required(statement.condition(), statement.text_range(), env)?
+            .is_truthy()
+        {
+            self.check_stop(statement.text_range())?;
+            let body_env = self.child(env);
+            if let Some(value) = self.block(body.as_ref(), body_env)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    fn for_stmt(&mut self, statement: &ForStmt, env: &Environment) -> Exec {
+        let env = self.child(env);
+        if let Some(initializer) = statement.initializer() {
+            self.statement(&initializer, &env)?;
+        }
+        let condition = statement.condition();
+        let increment = statement.increment();
+        let body = statement.body();
+        loop {
+            self.check_stop(statement.text_range())?;
+            if let Some(condition) = &condition {
+                if !self.expr(condition, &env)?.is_truthy() {
+                    return Ok(None);
+                }
+            }
+            let body_env = self.child(&env);
+            if let Some(value) = self.block(body.as_ref(), body_env)? {
+                return Ok(Some(value));
+            }
+            if let Some(increment) = &increment {
+                self.expr(increment, &env)?;
+            }
+        }
+    }
+
+    /// Evaluates an expression the parser may have left out of `parent`.
+    fn required(
+        &mut self,
+        expr: Option<Expr>,
+        parent: TextRange,
+        env: &Environment,
+    ) -> Result<Value, RuntimeError> {
+        match expr {
+            Some(expr) => self.expr(&expr, env),
+            None => error(parent, "Expected an expression".to_string()),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, env: &Environment) -> Result<Value, RuntimeError> {
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || self.nested_expr(expr, env))
+    }
+
+    fn nested_expr(&mut self, expr: &Expr, env: &Environment) -> Result<Value, RuntimeError> {
+        let range = expr.text_range();
+        match expr {
+            Expr::Literal(literal) => match literal.token().map(|token| token.kind()) {
+                Some(TokenType::True) => Ok(Value::Bool(true)),
+                Some(TokenType::False) => Ok(Value::Bool(false)),
+                Some(TokenType::Nil) => Ok(Value::Nil),
+                _ => match literal.value() {
+                    Some(LiteralValue::Number(number)) => Ok(Value::Number(number)),
+                    Some(LiteralValue::String(string)) => Ok(Value::String(string)),
+                    _ => error(range, "Expected an expression".to_string()),
+                },
+            },
+            Expr::NameRef(name_ref) => {
+                let name = name_ref.text();
+                match env.get(&name) {
+                    Some(value) => Ok(value),
+                    None => error(range, format!("Undefined variable `{}`", name)),
+                }
+            }
+            Expr::ParenExpr(paren) => self.required(paren.expr(), range, env),
+            Expr::PrefixExpr(prefix) => {
+                let operand = prefix.expr();
+                let value = self.required(operand.clone(), range, env)?;
+                match prefix.op_token().map(|token| token.kind()) {
+                    Some(TokenType::Bang) => Ok(Value::Bool(!value.is_truthy())),
+                    Some(TokenType::Minus) => {
+                        let operand_range = operand.map_or(range, |operand| operand.text_range());
+                        Ok(Value::Number(-number(operand_range, &value)?))
+                    }
+                    _ => error(range, "Expected an expression".to_string()),
+                }
+            }
+            Expr::BinaryExpr(binary) => self.binary(binary, env),
+            Expr::AssignExpr(assign) => {
+                let value = self.required(assign.value(), range, env)?;
+                match assign.target() {
+                    Some(Expr::NameRef(name_ref)) => {
+                        let name = name_ref.text();
+                        if !env.assign(&name, value.clone()) {
+                            return error(
+                                name_ref.text_range(),
+                                format!("Undefined variable `{}`", name),
+                            );
+                        }
+                    }
+                    Some(Expr::FieldExpr(field)) => {
+                        let (instance, name) = self.field_target(&field, env)?;
+                        instance.borrow_mut().fields.insert(name, value.clone());
+                    }
+                    _ => return error(range, "Invalid assignment target".to_string()),
+                }
+                Ok(value)
+            }
+            Expr::CallExpr(call) => self.call_expr(call, env),
+            Expr::FieldExpr(field) => {
+                let (instance, name) = self.field_target(field, env)?;
+                if let Some(value) = instance.borrow().fields.get(&name) {
+                    return Ok(value.clone());
+                }
+                let class = instance.borrow().class.clone();
+                match class.find_method(&name) {
+                    Some(method) => {
+                        let closure = self.child(&method.closure);
+                        Ok(Value::Function(
+                            method.bind(closure, Value::Instance(instance)),
+                        ))
+                    }
+                    None => error(range, format!("Undefined property `{}`", name)),
+                }
+            }
+            Expr::FunExpr(function) => Ok(Value::Function(Rc::new(Function::new(
+                function_name(function),
+                function.param_list(),
+                function.body(),
+                env.clone(),
+            )))),
+            Expr::ThisExpr(_) => match env.get("this") {
+                Some(this) => Ok(this),
+                None => error(range, "Cannot use `this` outside of a method".to_string()),
+            },
+            Expr::SuperExpr(super_expr) => self.super_expr(super_expr, env),
+        }
+    }
+
+    fn binary(&mut self, binary: &BinaryExpr, env: &Environment) -> Result<Value, RuntimeError> {
+        let range = binary.text_range();
+        let op = binary.op_token().map(|token| token.kind());
+        let lhs_expr = binary.lhs();
+        let rhs_expr = binary.rhs();
+        let lhs = self.required(lhs_expr.clone(), range, env)?;
+        match op {
+            Some(TokenType::And) if !lhs.is_truthy() => return Ok(lhs),
+            Some(TokenType::Or) if lhs.is_truthy() => return Ok(lhs),
+            Some(TokenType::And | TokenType::Or) => return self.required(rhs_expr, range, env),
+            _ => {}
+        }
+        let rhs = self.required(rhs_expr.clone(), range, env)?;
+        let lhs_range = lhs_expr.map_or(range, |expr| expr.text_range());
+        let rhs_range = rhs_expr.map_or(range, |expr| expr.text_range());
+        let numbers = || -> Result<(f64, f64), RuntimeError> {
+            Ok((number(lhs_range, &lhs)?, number(rhs_range, &rhs)?))
+        };
+        let value = match op {
+            Some(TokenType::Plus) => match (&lhs, &rhs) {
+                (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+                (Value::String(a), Value::String(b)) => Value::String(format!("{}{}", a, b)),
+                (Value::Number(_) | Value::String(_), Value::Number(_) | Value::String(_)) => {
+                    return error(
+                        range,
+                        format!("Cannot add {} and {}", lhs.type_name(), rhs.type_name()),
+                    )
+                }
+                (Value::Number(_) | Value::String(_), _) => return addable(rhs_range, &rhs),
+                _ => return addable(lhs_range, &lhs),
+            },
+            Some(TokenType::Minus) => numbers().map(|(a, b)| Value::Number(a - b))?,
+            Some(TokenType::Star) => numbers().map(|(a, b)| Value::Number(a * b))?,
+            Some(TokenType::Slash) => numbers().map(|(a, b)| Value::Number(a / b))?,
+            Some(TokenType::Less) => numbers().map(|(a, b)| Value::Bool(a < b))?,
+            Some(TokenType::LessEqual) => numbers().map(|(a, b)| Value::Bool(a <= b))?,
+            Some(TokenType::Greater) => numbers().map(|(a, b)| Value::Bool(a > b))?,
+            Some(TokenType::GreaterEqual) => numbers().map(|(a, b)| Value::Bool(a >= b))?,
+            Some(TokenType::EqualEqual) => Value::Bool(lhs == rhs),
+            Some(TokenType::BangEqual) => Value::Bool(lhs != rhs),
+            _ => return error(range, "Expected an operator".to_string()),
+        };
+        Ok(value)
+    }
+
+    fn call_expr(&mut self, call: &CallExpr, env: &Environment) -> Result<Value, RuntimeError> {
+        let range = call.text_range();
+        let callee = self.required(call.callee(), range, env)?;
+        let mut args = Vec::new();
+        for arg in call.arg_list().into_iter().flat_map(|args| args.args()) {
+            args.push(self.expr(&arg, env)?);
+        }
+        self.call(callee, args, range)
+    }
+
+    fn call(
+        &mut self,
+        callee: Value,
+        args: Vec<Value>,
+        range: TextRange,
+    ) -> Result<Value, RuntimeError> {
+        match callee {
+            Value::Builtin(_) => {
+                let line: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                let line = line.join(" ");
+                match &mut self.printer {
+                    Some(printer) => printer(&line),
+                    None => {
+                        self.output.push_str(&line);
+                        self.output.push('\n');
+                    }
+                }
+                Ok(Value::Nil)
+            }
+            Value::Function(function) => self.call_function(&function, args, range),
+            Value::Class(class) => {
+                let instance = Value::Instance(Rc::new(RefCell::new(Instance {
+                    class: class.clone(),
+                    fields: HashMap::new(),
+                })));
+                match class.find_method("init") {
+                    Some(init) => {
+                        let closure = self.child(&init.closure);
+                        self.call_function(&init.bind(closure, instance.clone()), args, range)?;
+                    }
+                    None => check_arity(0, args.len(), range)?,
+                }
+                Ok(instance)
+            }
+            other => error(
+                range,
+                format!("Cannot call a value of type {}", other.type_name()),
+            ),
+        }
+    }
+
+    fn call_function(
+        &mut self,
+        function: &Function,
+        args: Vec<Value>,
+        range: TextRange,
+    ) -> Result<Value, RuntimeError> {
+        check_arity(function.params.len(), args.len(), range)?;
+        if self.frames.len() > MAX_CALL_DEPTH {
+            return error(range, "Stack overflow".to_string());
+        }
+        let env = self.child(&function.closure);
+        for (param, arg) in function.params.iter().zip(args) {
+            env.define(param, arg);
+        }
+        self.frames.push(Frame {
+            name: function.name.clone(),
+            range,
+            env: env.clone(),
+        });
+        let returned = self.block(function.body.as_ref(), env);
+        self.frames.pop();
+        let returned = returned?.unwrap_or(Value::Nil);
+        match function.is_initializer {
+            true => Ok(function.closure.get("this").unwrap_or(Value::Nil)),
+            false => Ok(returned),
+        }
+    }
+
+    /// The instance whose field `field` reads or writes, and the field name.
+    fn field_target(
+        &mut self,
+        field: &FieldExpr,
+        env: &Environment,
+    ) -> Result<(Rc<RefCell<Instance>>, String), RuntimeError> {
+        let range = field.text_range();
+        let receiver = field.receiver();
+        let value = self.required(receiver.clone(), range, env)?;
+        let Value::Instance(instance) = value else {
+            return error(
+                receiver.map_or(range, |receiver| receiver.text_range()),
+                format!("Only instances have fields, found {}", value.type_name()),
+            );
+        };
+        match field.name_ref() {
+            Some(name) => Ok((instance, name.text())),
+            None => error(range, "Expected a field name".to_string()),
+        }
+    }
+
+    fn super_expr(
+        &mut self,
+        super_expr: &SuperExpr,
+        env: &Environment,
+    ) -> Result<Value, RuntimeError> {
+        let range = super_expr.text_range();
+        let (Some(Value::Class(superclass)), Some(this)) = (env.get("super"), env.get("this"))
+        else {
+            return error(
+                range,
+                "Cannot use `super` outside of a subclass".to_string(),
+            );
+        };
+        let Some(name) = super_expr.name_ref().map(|name| name.text()) else {
+            return error(range, "Expected a method name".to_string());
+        };
+        match superclass.find_method(&name) {
+            Some(method) => {
+                let closure = self.child(&method.closure);
+                Ok(Value::Function(method.bind(closure, this)))
+            }
+            None => error(range, format!("Undefined property `{}`", name)),
+        }
+    }
+}
+
+/// Closures point at the scopes they were created in, which may hold them
+/// in turn, so the bindings are dropped to free both.
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        self.globals.clear();
+        for env in self.environments.drain(..) {
+            if let Some(env) = env.upgrade() {
+                Environment(env).clear();
+            }
+        }
+    }
+}
+
+/// Functions take the name of the variable they are declared with.
+fn function_name(function: &FunExpr) -> String {
+    function
+        .syntax()
+        .parent()
+        .and_then(VarDecl::cast)
+        .and_then(|declaration| declaration.name())
+        .map_or("anonymous".to_string(), |name| name.text())
+}
+
+fn number(range: TextRange, value: &Value) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Number(number) => Ok(*number),
+        other => error(
+            range,
+            format!("Expected a number, found {}", other.type_name()),
+        ),
+    }
+}
+
+fn addable(range: TextRange, value: &Value) -> Result<Value, RuntimeError> {
+    error(
+        range,
+        format!("Expected a number or a string, found {}", value.type_name()),
+    )
+}
+
+fn check_arity(expected: usize, found: usize, range: TextRange) -> Result<(), RuntimeError> {
+    match expected == found {
+        true => Ok(()),
+        false => error(
+            range,
+            format!(
+                "Expected {} argument{}, found {}",
+                expected,
+                if expected == 1 { "" } else { "s" },
+                found
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::parser::parse;
+
+    use super::*;
+
+    fn run(source: &str) -> Result<String, (String, String)> {
+        let parse = parse(source);
+        assert_eq!(parse.errors, vec![]);
+        let mut interpreter = Interpreter::new();
+        match interpreter.run(&parse.tree()) {
+            Ok(()) => Ok(std::mem::take(&mut interpreter.output)),
+            Err(error) => Err((
+                error.message,
+                source[error.range.start..error.range.end].to_string(),
+            )),
+        }
+    }
+
+    #[test]
+    fn should_print_arithmetic_and_strings() {
+        let source = concat!(
+            "severo a = 1 + 2 * 3\n",
+            "print(a, -a / 2, a >= 7, !nil)\n",
+            "severo name = \"Sev\" + \"ero\"\n",
+            "print(name == \"Severo\", name != \"Severo\")\n",
+            "print(nil or \"default\", false and 1)\n",
+        );
+        assert_eq!(
+            run(source),
+            Ok("7 -3.5 true true\ntrue false\ndefault false\n".to_string())
+        );
+    }
+
+    #[test]
+    fn should_run_control_flow() {
+        let source = concat!(
+            "for (severo i = 0; i < 4; i = i + 1) {\n",
+            "    if (i == 0) { print(\"zero\") }\n",
+            "    elif (i == 1) { print(\"one\") }\n",
+            "    else { print(i) }\n",
+            "}\n",
+            "severo n = 3\n",
+            "while (n > 0) { n = n - 1 }\n",
+            "print(n)\n",
+        );
+        assert_eq!(run(source), Ok("zero\none\n2\n3\n0\n".to_string()));
+    }
+
+    #[test]
+    fn should_call_functions_and_closures() {
+        let source = concat!(
+            "severo fib = fun(n) {\n",
+            "    if (n < 2) { return n }\n",
+            "    return fib(n - 1) + fib(n - 2)\n",
+            "}\n",
+            "severo counter = fun() {\n",
+            "    severo count = 0\n",
+            "    return fun() {\n",
+            "        count = count + 1\n",
+            "        return count\n",
+            "    }\n",
+            "}\n",
+            "severo next = counter()\n",
+            "next()\n",
+            "print(fib(10), next(), fib)\n",
+        );
+        assert_eq!(run(source), Ok("55 2 <fun fib>\n".to_string()));
+    }
+
+    #[test]
+    fn should_run_classes_with_inheritance() {
+        let source = concat!(
+            "class Animal {\n",
+            "    init(name) { this.name = name }\n",
+            "    speak() { return this.name + \" makes a sound\" }\n",
+            "}\n",
+            "class Dog < Animal {\n",
+            "    speak() { return super.speak() + \" and barks\" }\n",
+            "}\n",
+            "severo dog = Dog(\"Rex\")\n",
+            "print(dog.speak())\n",
+            "print(dog, Dog)\n",
+        );
+        assert_eq!(
+            run(source),
+            Ok("Rex makes a sound and barks\nDog instance Dog\n".to_string())
+        );
+    }
+
+    #[test]
+    fn should_report_runtime_errors_at_the_failing_expression() {
+        assert_eq!(
+            run("severo a = \"a\" - 1"),
+            Err((
+                "Expected a number, found string".to_string(),
+                "\"a\"".to_string()
+            ))
+        );
+        assert_eq!(
+            run("print(1)\nprint(missing)"),
+            Err((
+                "Undefined variable `missing`".to_string(),
+                "missing".to_string()
+            ))
+        );
+        assert_eq!(
+            run("severo f = fun(a) {}\nf()"),
+            Err((
+                "Expected 1 argument, found 0".to_string(),
+                "f()".to_string()
+            ))
+        );
+        assert_eq!(
+            run("severo f = fun() { return f() }\nf()"),
+            Err(("Stack overflow".to_string(), "f()".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_run_deep_recursion() {
+        let source = concat!(
+            "severo count = fun(n) {\n",
+            "    if (n == 0) { return 0 }\n",
+            "    return count(n - 1) + 1\n",
+            "}\n",
+            "print(count(900))\n",
+        );
+        assert_eq!(run(source), Ok("900\n".to_string()));
+    }
+
+    #[test]
+    fn should_run_deeply_nested_code() {
+        let expression = format!("{}1{}", "(".repeat(3000), ")".repeat(3000));
+        assert_eq!(
+            run(&format!("print({})", expression)),
+            Ok("1\n".to_string())
+        );
+        let block = format!("{}print(2){}", "{".repeat(3000), "}".repeat(3000));
+        assert_eq!(run(&block), Ok("2\n".to_string()));
+    }
+
+    #[test]
+    fn should_drop_long_chains_of_values() {
+        let source = concat!(
+            "class Node {\n",
+            "    init(next) { this.next = next }\n",
+            "}\n",
+            "severo wrap = fun(inner) { return fun() { return inner } }\n",
+            "severo list = nil\n",
+            "severo chain = nil\n",
+            "for (severo i = 0; i < 20000; i = i + 1) {\n",
+            "    list = Node(list)\n",
+            "    chain = wrap(chain)\n",
+            "}\n",
+            "list = nil\n",
+            "print(\"done\")\n",
+        );
+        assert_eq!(run(source), Ok("done\n".to_string()));
+    }
+
+    #[test]
+    fn should_free_closures_and_their_scopes() {
+        let source = concat!(
+            "severo counter = fun() {\n",
+            "    severo count = 0\n",
+            "    severo next = fun() {\n",
+            "        count = count + 1\n",
+            "        return count\n",
+            "    }\n",
+            "    return next\n",
+            "}\n",
+            "class Point {\n",
+            "    init() { this.show = this.describe }\n",
+            "    describe() { return \"point\" }\n",
+            "}\n",
+            "severo next = counter()\n",
+            "severo point = Point()\n",
+        );
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&parse(source).tree()).unwrap();
+        let scopes: Vec<Weak<RefCell<Scope>>> =
+            std::iter::once(Rc::downgrade(&interpreter.globals.0))
+                .chain(interpreter.environments.iter().cloned())
+                .collect();
+        drop(interpreter);
+        assert!(scopes.iter().all(|scope| scope.upgrade().is_none()));
+    }
+}
@@ -10,5 +10,21 @@ pub fn get_keywords_hash() -> HashMap<&'static str, TokenType> {
     let mut keywords_map = HashMap::new();
     keywords_map.insert("severo", TokenType::Var);
     keywords_map.insert("print", TokenType::Print);
+    keywords_map.insert("fun", TokenType::Fun);
+    keywords_map.insert("class", TokenType::Class);
+    keywords_map.insert("if", TokenType::If);
+    keywords_map.insert("elif", TokenType::Elif);
+    keywords_map.insert("else", TokenType::Else);
+    keywords_map.insert("while", TokenType::While);
+    keywords_map.insert("for", TokenType::For);
+    keywords_map.insert("in", TokenType::In);
+    keywords_map.insert("return", TokenType::Return);
+    keywords_map.insert("this", TokenType::This);
+    keywords_map.insert("super", TokenType::Super);
+    keywords_map.insert("true", TokenType::True);
+    keywords_map.insert("false", TokenType::False);
+    keywords_map.insert("nil", TokenType::Nil);
+    keywords_map.insert("and", TokenType::And);
+    keywords_map.insert("or", TokenType::Or);
     keywords_map
 }
@@ -0,0 +1,123 @@
+use tower_lsp::lsp_types::{Position, Range};
+
+use super::{syntax::TextRange, types::Location};
+
+/// Converts between byte offsets in a source and LSP line/UTF-16 positions.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    source: String,
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let bytes = source.as_bytes();
+        for (index, byte) in bytes.iter().enumerate() {
+            match byte {
+                b'\n' => line_starts.push(index + 1),
+                b'\r' if bytes.get(index + 1) != Some(&b'\n') => line_starts.push(index + 1),
+                _ => {}
+            }
+        }
+        LineIndex {
+            source: source.to_string(),
+            line_starts,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub fn location(&self, offset: usize) -> Location {
+        let offset = offset.min(self.source.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+        let col = self.source[line_start..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        Location { offset, line, col }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        self.location(offset).into()
+    }
+
+    pub fn range(&self, range: TextRange) -> Range {
+        Range {
+            start: self.position(range.start),
+            end: self.position(range.end),
+        }
+    }
+
+    /// Returns the byte offset of `position`, clamping the column to the end
+    /// of its line. Positions past the last line are `None`.
+    pub fn offset(&self, position: Position) -> Option<usize> {
+        let line_start = *self.line_starts.get(position.line as usize)?;
+        let line_end = self.line_end(position.line as usize);
+        let mut col = 0;
+        for (index, char) in self.source[line_start..line_end].char_indices() {
+            if col >= position.character as usize {
+                return Some(line_start + index);
+            }
+            col += char.len_utf16();
+        }
+        Some(line_end)
+    }
+
+    /// Byte offset of the end of `line`, before its line break.
+    pub fn line_end(&self, line: usize) -> usize {
+        let next_start = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.source.len());
+        let text = &self.source[self.line_starts[line]..next_start];
+        let trimmed = text.trim_end_matches(['\n', '\r']);
+        self.line_starts[line] + trimmed.len()
+    }
+
+    pub fn line_start(&self, line: usize) -> usize {
+        self.line_starts[line]
+    }
+
+    pub fn line_text(&self, line: usize) -> &str {
+        &self.source[self.line_start(line)..self.line_end(line)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_convert_offsets_to_positions() {
+        let index = LineIndex::new("ab\r\ncé😀d\rx");
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.position(4), Position::new(1, 0));
+        assert_eq!(index.position(12), Position::new(1, 5));
+        assert_eq!(index.position(13), Position::new(2, 0));
+    }
+
+    #[test]
+    fn should_convert_positions_to_offsets() {
+        let index = LineIndex::new("ab\r\ncé😀d\nx");
+        assert_eq!(index.offset(Position::new(1, 2)), Some(7));
+        assert_eq!(index.offset(Position::new(1, 4)), Some(11));
+        assert_eq!(index.offset(Position::new(1, 99)), Some(12));
+        assert_eq!(index.offset(Position::new(3, 0)), None);
+    }
+
+    #[test]
+    fn should_return_line_text_without_line_breaks() {
+        let index = LineIndex::new("one\r\ntwo\n");
+        assert_eq!(index.line_text(0), "one");
+        assert_eq!(index.line_text(1), "two");
+        assert_eq!(index.line_text(2), "");
+    }
+}
@@ -1,6 +1,14 @@
+pub mod ast;
 pub mod builtin_functions;
+pub mod constant;
+pub mod formatter;
 pub mod helpers;
+pub mod inference;
+pub mod interpreter;
 pub mod keywords;
+pub mod line_index;
 pub mod parser;
+pub mod resolver;
 pub mod scanner;
+pub mod syntax;
 pub mod types;
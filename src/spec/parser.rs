@@ -1,6 +1,17 @@
-use super::types::{Literal, Token, TokenType};
+use std::{collections::HashMap, rc::Rc};
+
+use super::{
+    ast::{AstNode, SourceFile},
+    scanner::scan_tokens_lossless,
+    syntax::{GreenNode, NodeKind, SyntaxNode, TextRange, TreeBuilder},
+    types::{Literal, Token, TokenType},
+};
 
 pub fn get_vars(tokens: Vec<Token>) -> Vec<String> {
+    let tokens: Vec<Token> = tokens
+        .into_iter()
+        .filter(|token| !token.token_type.is_comment())
+        .collect();
     let mut vars = Vec::new();
     let mut index = 0;
 
@@ -25,9 +36,640 @@ pub fn get_vars(tokens: Vec<Token>) -> Vec<String> {
     vars
 }
 
+pub fn get_docs(tokens: Vec<Token>) -> HashMap<String, String> {
+    let mut docs = HashMap::new();
+    let mut doc_lines: Vec<String> = Vec::new();
+    let mut last_doc_line = 0;
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let token = tokens.get(index).unwrap();
+        match token.token_type {
+            TokenType::DocComment => {
+                if !doc_lines.is_empty() && last_doc_line + 1 != token.span.start.line {
+                    doc_lines.clear();
+                }
+                if let Some(Literal::Comment(text)) = &token.literal {
+                    doc_lines.push(text.clone());
+                }
+                last_doc_line = token.span.start.line;
+            }
+            TokenType::Var
+                if !doc_lines.is_empty() && last_doc_line + 1 == token.span.start.line =>
+            {
+                let next_token = tokens[index + 1..]
+                    .iter()
+                    .find(|token| !token.token_type.is_comment());
+                if let Some(Token {
+                    token_type: TokenType::Identifier,
+                    literal: Some(Literal::Identifier(var_name)),
+                    ..
+                }) = next_token
+                {
+                    docs.insert(var_name.clone(), doc_lines.join("\n"));
+                }
+                doc_lines.clear();
+            }
+            _ => doc_lines.clear(),
+        }
+        index += 1;
+    }
+
+    docs
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub range: TextRange,
+}
+
+pub struct Parse {
+    pub green: Rc<GreenNode>,
+    pub errors: Vec<ParseError>,
+}
+
+impl Parse {
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn tree(&self) -> SourceFile {
+        SourceFile::cast(self.syntax()).unwrap()
+    }
+}
+
+/// Parses a whole file into a lossless syntax tree. Parsing never fails:
+/// unexpected tokens end up in `Error` nodes and are listed in `errors`.
+pub fn parse(source: &str) -> Parse {
+    let mut tokens = scan_tokens_lossless(source.to_string()).tokens;
+    tokens.pop();
+    let mut parser = Parser {
+        source,
+        tokens,
+        position: 0,
+        builder: TreeBuilder::default(),
+        errors: Vec::new(),
+    };
+    parser.source_file();
+    Parse {
+        green: parser.builder.finish(),
+        errors: parser.errors,
+    }
+}
+
+const ASSIGNMENT_BINDING_POWER: u8 = 1;
+const PREFIX_BINDING_POWER: u8 = 15;
+
+fn infix_binding_power(token_type: TokenType) -> Option<(u8, u8)> {
+    match token_type {
+        TokenType::Or => Some((3, 4)),
+        TokenType::And => Some((5, 6)),
+        TokenType::EqualEqual | TokenType::BangEqual => Some((7, 8)),
+        TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual => {
+            Some((9, 10))
+        }
+        TokenType::Plus | TokenType::Minus => Some((11, 12)),
+        TokenType::Star | TokenType::Slash => Some((13, 14)),
+        _ => None,
+    }
+}
+
+pub fn can_start_expression(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Number
+            | TokenType::String
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+            | TokenType::Identifier
+            | TokenType::Print
+            | TokenType::This
+            | TokenType::Super
+            | TokenType::LeftParen
+            | TokenType::Fun
+            | TokenType::Bang
+            | TokenType::Minus
+    )
+}
+
+pub fn describe(token_type: TokenType) -> &'static str {
+    match token_type {
+        TokenType::Var => "`severo`",
+        TokenType::Identifier => "identifier",
+        TokenType::String => "string",
+        TokenType::Number => "number",
+        TokenType::Print => "`print`",
+        TokenType::Fun => "`fun`",
+        TokenType::Class => "`class`",
+        TokenType::If => "`if`",
+        TokenType::Elif => "`elif`",
+        TokenType::Else => "`else`",
+        TokenType::While => "`while`",
+        TokenType::For => "`for`",
+        TokenType::In => "`in`",
+        TokenType::Return => "`return`",
+        TokenType::This => "`this`",
+        TokenType::Super => "`super`",
+        TokenType::True => "`true`",
+        TokenType::False => "`false`",
+        TokenType::Nil => "`nil`",
+        TokenType::And => "`and`",
+        TokenType::Or => "`or`",
+        TokenType::Equal => "`=`",
+        TokenType::EqualEqual => "`==`",
+        TokenType::Bang => "`!`",
+        TokenType::BangEqual => "`!=`",
+        TokenType::Less => "`<`",
+        TokenType::LessEqual => "`<=`",
+        TokenType::Greater => "`>`",
+        TokenType::GreaterEqual => "`>=`",
+        TokenType::Plus => "`+`",
+        TokenType::Minus => "`-`",
+        TokenType::Star => "`*`",
+        TokenType::Slash => "`/`",
+        TokenType::LeftParen => "`(`",
+        TokenType::RightParen => "`)`",
+        TokenType::LeftBrace => "`{`",
+        TokenType::RightBrace => "`}`",
+        TokenType::Comma => "`,`",
+        TokenType::Dot => "`.`",
+        TokenType::Semicolon => "`;`",
+        TokenType::LineComment | TokenType::BlockComment | TokenType::DocComment => "comment",
+        TokenType::Whitespace | TokenType::Newline => "whitespace",
+        TokenType::Error => "invalid text",
+        TokenType::Eof => "end of file",
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    builder: TreeBuilder,
+    errors: Vec<ParseError>,
+}
+
+impl Parser<'_> {
+    fn source_file(&mut self) {
+        self.builder.start_node(NodeKind::SourceFile);
+        while !self.at(TokenType::Eof) {
+            self.statement();
+        }
+        self.bump_trivia();
+        self.builder.finish_node();
+    }
+
+    fn statement(&mut self) {
+        match self.current() {
+            TokenType::Var => self.var_decl(),
+            TokenType::Class => self.class_decl(),
+            TokenType::If => self.if_stmt(),
+            TokenType::While => self.while_stmt(),
+            TokenType::For => self.for_stmt(),
+            TokenType::Return => self.return_stmt(),
+            TokenType::LeftBrace => self.block(),
+            token_type if can_start_expression(token_type) => {
+                self.start_node(NodeKind::ExprStmt);
+                self.expression();
+                self.builder.finish_node();
+            }
+            token_type => {
+                self.error_token(format!(
+                    "Expected a statement, found {}",
+                    describe(token_type)
+                ));
+            }
+        }
+        self.eat(TokenType::Semicolon);
+    }
+
+    fn var_decl(&mut self) {
+        self.start_node(NodeKind::VarDecl);
+        self.bump();
+        self.name("Expected a variable name");
+        if self.eat(TokenType::Equal) {
+            self.expect_expression();
+        }
+        self.builder.finish_node();
+    }
+
+    fn class_decl(&mut self) {
+        self.start_node(NodeKind::ClassDecl);
+        self.bump();
+        self.name("Expected a class name");
+        if self.eat(TokenType::Less) {
+            match self.at(TokenType::Identifier) {
+                true => self.name_ref(),
+                false => self.error("Expected a superclass name"),
+            }
+        }
+        if self.expect(TokenType::LeftBrace) {
+            while !self.at(TokenType::RightBrace) && !self.at(TokenType::Eof) {
+                match self.at(TokenType::Identifier) {
+                    true => self.method(),
+                    false => self.error_token(format!(
+                        "Expected a method, found {}",
+                        describe(self.current())
+                    )),
+                }
+            }
+            self.expect(TokenType::RightBrace);
+        }
+        self.builder.finish_node();
+    }
+
+    fn method(&mut self) {
+        self.start_node(NodeKind::Method);
+        self.name("Expected a method name");
+        self.param_list();
+        self.block();
+        self.builder.finish_node();
+    }
+
+    fn param_list(&mut self) {
+        self.start_node(NodeKind::ParamList);
+        if self.expect(TokenType::LeftParen) {
+            while self.at(TokenType::Identifier) {
+                self.name("Expected a parameter name");
+                if !self.eat(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.expect(TokenType::RightParen);
+        }
+        self.builder.finish_node();
+    }
+
+    fn block(&mut self) {
+        self.start_node(NodeKind::Block);
+        if self.expect(TokenType::LeftBrace) {
+            while !self.at(TokenType::RightBrace) && !self.at(TokenType::Eof) {
+                self.statement();
+            }
+            self.expect(TokenType::RightBrace);
+        }
+        self.builder.finish_node();
+    }
+
+    fn if_stmt(&mut self) {
+        self.start_node(NodeKind::IfStmt);
+        self.bump();
+        self.expect_expression();
+        self.block();
+        while self.at(TokenType::Elif) {
+            self.start_node(NodeKind::ElifBranch);
+            self.bump();
+            self.expect_expression();
+            self.block();
+            self.builder.finish_node();
+        }
+        if self.at(TokenType::Else) {
+            self.start_node(NodeKind::ElseBranch);
+            self.bump();
+            self.block();
+            self.builder.finish_node();
+        }
+        self.builder.finish_node();
+    }
+
+    fn while_stmt(&mut self) {
+        self.start_node(NodeKind::WhileStmt);
+        self.bump();
+        self.expect_expression();
+        self.block();
+        self.builder.finish_node();
+    }
+
+    fn for_stmt(&mut self) {
+        self.start_node(NodeKind::ForStmt);
+        self.bump();
+        self.expect(TokenType::LeftParen);
+        match self.current() {
+            TokenType::Semicolon => {}
+            TokenType::Var => self.var_decl(),
+            _ => {
+                self.start_node(NodeKind::ExprStmt);
+                self.expect_expression();
+                self.builder.finish_node();
+            }
+        }
+        self.expect(TokenType::Semicolon);
+        if !self.at(TokenType::Semicolon) {
+            self.expect_expression();
+        }
+        self.expect(TokenType::Semicolon);
+        if !self.at(TokenType::RightParen) {
+            self.expect_expression();
+        }
+        self.expect(TokenType::RightParen);
+        self.block();
+        self.builder.finish_node();
+    }
+
+    fn return_stmt(&mut self) {
+        self.start_node(NodeKind::ReturnStmt);
+        self.bump();
+        if !self.at_line_start() && can_start_expression(self.current()) {
+            self.expression();
+        }
+        self.builder.finish_node();
+    }
+
+    fn name(&mut self, message: &str) {
+        match self.at(TokenType::Identifier) {
+            true => {
+                self.start_node(NodeKind::Name);
+                self.bump();
+                self.builder.finish_node();
+            }
+            false => self.error(message),
+        }
+    }
+
+    fn name_ref(&mut self) {
+        self.start_node(NodeKind::NameRef);
+        self.bump();
+        self.builder.finish_node();
+    }
+
+    fn expect_expression(&mut self) {
+        match can_start_expression(self.current()) {
+            true => self.expression(),
+            false => self.error("Expected an expression"),
+        }
+    }
+
+    fn expression(&mut self) {
+        self.expression_bp(0);
+    }
+
+    fn expression_bp(&mut self, min_binding_power: u8) {
+        let checkpoint = self.checkpoint();
+        let start = self.current_offset();
+        if matches!(self.current(), TokenType::Bang | TokenType::Minus) {
+            self.start_node(NodeKind::PrefixExpr);
+            self.bump();
+            self.expect_expression_bp(PREFIX_BINDING_POWER);
+            self.builder.finish_node();
+        } else {
+            self.postfix();
+        }
+
+        loop {
+            if self.at_line_start() {
+                break;
+            }
+            let operator = self.current();
+            if operator == TokenType::Equal {
+                if min_binding_power > ASSIGNMENT_BINDING_POWER {
+                    break;
+                }
+                let target = self.builder.last_node_kind();
+                let target_range = TextRange::new(start, self.previous_end());
+                if !matches!(target, Some(NodeKind::NameRef | NodeKind::FieldExpr)) {
+                    self.errors.push(ParseError {
+                        message: "Invalid assignment target".to_string(),
+                        range: target_range,
+                    });
+                }
+                self.builder.start_node_at(checkpoint, NodeKind::AssignExpr);
+                self.bump();
+                self.expect_expression_bp(ASSIGNMENT_BINDING_POWER);
+                self.builder.finish_node();
+                break;
+            }
+            let Some((left_binding_power, right_binding_power)) = infix_binding_power(operator)
+            else {
+                break;
+            };
+            if left_binding_power < min_binding_power {
+                break;
+            }
+            self.builder.start_node_at(checkpoint, NodeKind::BinaryExpr);
+            self.bump();
+            self.expect_expression_bp(right_binding_power);
+            self.builder.finish_node();
+        }
+    }
+
+    fn expect_expression_bp(&mut self, min_binding_power: u8) {
+        match can_start_expression(self.current()) {
+            true => self.expression_bp(min_binding_power),
+            false => self.error("Expected an expression"),
+        }
+    }
+
+    fn postfix(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.primary();
+        while !self.at_line_start() {
+            match self.current() {
+                TokenType::LeftParen => {
+                    self.builder.start_node_at(checkpoint, NodeKind::CallExpr);
+                    self.arg_list();
+                    self.builder.finish_node();
+                }
+                TokenType::Dot => {
+                    self.builder.start_node_at(checkpoint, NodeKind::FieldExpr);
+                    self.bump();
+                    match self.at(TokenType::Identifier) {
+                        true => self.name_ref(),
+                        false => self.error("Expected a property name"),
+                    }
+                    self.builder.finish_node();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn arg_list(&mut self) {
+        self.start_node(NodeKind::ArgList);
+        self.bump();
+        while can_start_expression(self.current()) {
+            self.expression();
+            if !self.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenType::RightParen);
+        self.builder.finish_node();
+    }
+
+    fn primary(&mut self) {
+        match self.current() {
+            TokenType::Number
+            | TokenType::String
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil => {
+                self.start_node(NodeKind::Literal);
+                self.bump();
+                self.builder.finish_node();
+            }
+            TokenType::Identifier | TokenType::Print => self.name_ref(),
+            TokenType::This => {
+                self.start_node(NodeKind::ThisExpr);
+                self.bump();
+                self.builder.finish_node();
+            }
+            TokenType::Super => {
+                self.start_node(NodeKind::SuperExpr);
+                self.bump();
+                if self.expect(TokenType::Dot) {
+                    match self.at(TokenType::Identifier) {
+                        true => self.name_ref(),
+                        false => self.error("Expected a method name"),
+                    }
+                }
+                self.builder.finish_node();
+            }
+            TokenType::LeftParen => {
+                self.start_node(NodeKind::ParenExpr);
+                self.bump();
+                self.expect_expression();
+                self.expect(TokenType::RightParen);
+                self.builder.finish_node();
+            }
+            TokenType::Fun => {
+                self.start_node(NodeKind::FunExpr);
+                self.bump();
+                self.param_list();
+                self.block();
+                self.builder.finish_node();
+            }
+            _ => self.error("Expected an expression"),
+        }
+    }
+
+    fn nth_significant(&self, mut index: usize) -> Option<&Token> {
+        while let Some(token) = self.tokens.get(index) {
+            if !token.token_type.is_trivia() {
+                return Some(token);
+            }
+            index += 1;
+        }
+        None
+    }
+
+    fn current(&self) -> TokenType {
+        self.nth_significant(self.position)
+            .map_or(TokenType::Eof, |token| token.token_type)
+    }
+
+    fn at(&self, token_type: TokenType) -> bool {
+        self.current() == token_type
+    }
+
+    /// Whether a line break separates the current token from the previous
+    /// significant one. Expressions never continue across one, which is how
+    /// statements end without semicolons.
+    fn at_line_start(&self) -> bool {
+        self.tokens[self.position..]
+            .iter()
+            .take_while(|token| token.token_type.is_trivia())
+            .any(|token| {
+                token.token_type == TokenType::Newline
+                    || (token.token_type == TokenType::BlockComment
+                        && token.span.start.line != token.span.end.line)
+            })
+    }
+
+    fn bump_trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.position) {
+            if !token.token_type.is_trivia() {
+                break;
+            }
+            let text = &self.source[token.span.start.offset..token.span.end.offset];
+            self.builder.token(token, text);
+            self.position += 1;
+        }
+    }
+
+    fn bump(&mut self) {
+        self.bump_trivia();
+        if let Some(token) = self.tokens.get(self.position) {
+            let text = &self.source[token.span.start.offset..token.span.end.offset];
+            self.builder.token(token, text);
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, token_type: TokenType) -> bool {
+        match self.at(token_type) {
+            true => {
+                self.bump();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn expect(&mut self, token_type: TokenType) -> bool {
+        match self.eat(token_type) {
+            true => true,
+            false => {
+                self.error(&format!("Expected {}", describe(token_type)));
+                false
+            }
+        }
+    }
+
+    fn start_node(&mut self, kind: NodeKind) {
+        self.bump_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn checkpoint(&mut self) -> usize {
+        self.bump_trivia();
+        self.builder.checkpoint()
+    }
+
+    fn previous_end(&self) -> usize {
+        self.tokens[..self.position]
+            .iter()
+            .rev()
+            .find(|token| !token.token_type.is_trivia())
+            .map_or(0, |token| token.span.end.offset)
+    }
+
+    fn current_offset(&self) -> usize {
+        self.nth_significant(self.position)
+            .map_or(self.source.len(), |token| token.span.start.offset)
+    }
+
+    /// Reports an error right after the previous significant token, which is
+    /// where the missing piece was expected.
+    fn error(&mut self, message: &str) {
+        let offset = self.previous_end();
+        self.errors.push(ParseError {
+            message: message.to_string(),
+            range: TextRange::empty(offset),
+        });
+    }
+
+    fn error_token(&mut self, message: String) {
+        self.start_node(NodeKind::Error);
+        let range = self
+            .nth_significant(self.position)
+            .map_or(TextRange::empty(self.source.len()), |token| {
+                TextRange::new(token.span.start.offset, token.span.end.offset)
+            });
+        self.bump();
+        self.builder.finish_node();
+        self.errors.push(ParseError { message, range });
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::spec::types::{Literal, Token, TokenType};
+    use crate::spec::{
+        scanner::scan_tokens,
+        syntax::{NodeKind, TextRange},
+        types::{Literal, Span, Token, TokenType},
+    };
 
     use super::*;
 
@@ -37,14 +679,12 @@ mod tests {
         let tokens = vec![
             Token {
                 token_type: TokenType::Var,
-                line: 0,
-                col: 0,
+                span: Span::default(),
                 literal: None,
             },
             Token {
                 token_type: TokenType::Identifier,
-                line: 0,
-                col: 0,
+                span: Span::default(),
                 literal: Some(Literal::Identifier(variable_name.clone())),
             },
         ];
@@ -53,12 +693,171 @@ mod tests {
         assert_eq!(expected_vars, found_vars);
     }
 
+    #[test]
+    fn should_ignore_variables_inside_comments() {
+        let source = "// severo hidden\n/* severo alsoHidden */\nsevero /* note */ shown";
+        let tokens = scan_tokens(source.to_string()).tokens;
+        let found_vars = get_vars(tokens);
+        assert_eq!(vec!["shown".to_string()], found_vars);
+    }
+
+    #[test]
+    fn should_associate_doc_comments_with_the_next_declaration() {
+        let source = "/// Counts things.\n/// Starts at zero.\nsevero count = 0\n\n/// Detached.\n\nsevero other = 1";
+        let tokens = scan_tokens(source.to_string()).tokens;
+        let docs = get_docs(tokens);
+        assert_eq!(docs.len(), 1);
+        assert_eq!(
+            docs.get("count"),
+            Some(&"Counts things.\nStarts at zero.".to_string())
+        );
+    }
+
+    #[test]
+    fn should_parse_a_declaration_into_a_tree() {
+        let parse = parse("severo a = 1 + 2 * 3\nprint(a)");
+        assert!(parse.errors.is_empty());
+        assert_eq!(
+            parse.syntax().debug_tree(),
+            r#"SourceFile@0..29
+  VarDecl@0..20
+    Var@0..6 "severo"
+    Whitespace@6..7 " "
+    Name@7..8
+      Identifier@7..8 "a"
+    Whitespace@8..9 " "
+    Equal@9..10 "="
+    Whitespace@10..11 " "
+    BinaryExpr@11..20
+      Literal@11..12
+        Number@11..12 "1"
+      Whitespace@12..13 " "
+      Plus@13..14 "+"
+      Whitespace@14..15 " "
+      BinaryExpr@15..20
+        Literal@15..16
+          Number@15..16 "2"
+        Whitespace@16..17 " "
+        Star@17..18 "*"
+        Whitespace@18..19 " "
+        Literal@19..20
+          Number@19..20 "3"
+  Newline@20..21 "\n"
+  ExprStmt@21..29
+    CallExpr@21..29
+      NameRef@21..26
+        Print@21..26 "print"
+      ArgList@26..29
+        LeftParen@26..27 "("
+        NameRef@27..28
+          Identifier@27..28 "a"
+        RightParen@28..29 ")"
+"#
+        );
+    }
+
+    #[test]
+    fn should_parse_statements_and_classes() {
+        let source = r#"
+class Greeter < Base {
+  init(name) { this.name = name }
+  greet() { print(super.greet(), this.name) }
+}
+severo count = 0
+while count < 3 { count = count + 1 }
+for (severo i = 0; i < 3; i = i + 1) { print(i) }
+if !done { return } elif count >= 3 and ready { print("x") } else { print(-1) }
+"#;
+        let parse = parse(source);
+        assert_eq!(parse.errors, vec![]);
+        let kinds: Vec<NodeKind> = parse.syntax().children().map(|node| node.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::ClassDecl,
+                NodeKind::VarDecl,
+                NodeKind::WhileStmt,
+                NodeKind::ForStmt,
+                NodeKind::IfStmt,
+            ]
+        );
+    }
+
+    #[test]
+    fn should_end_statements_at_line_breaks() {
+        let parse = parse("severo a = b\n-1\nreturn\nprint(a)");
+        let kinds: Vec<NodeKind> = parse.syntax().children().map(|node| node.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::VarDecl,
+                NodeKind::ExprStmt,
+                NodeKind::ReturnStmt,
+                NodeKind::ExprStmt,
+            ]
+        );
+    }
+
+    #[test]
+    fn should_recover_from_errors() {
+        let source = "severo = \nprint(1 +)\n1 = 2\n)\nfoo(1, 2";
+        let parse = parse(source);
+        assert_eq!(parse.syntax().text(), source);
+        assert_eq!(
+            parse.errors,
+            vec![
+                ParseError {
+                    message: "Expected a variable name".to_string(),
+                    range: TextRange::empty(6),
+                },
+                ParseError {
+                    message: "Expected an expression".to_string(),
+                    range: TextRange::empty(19),
+                },
+                ParseError {
+                    message: "Invalid assignment target".to_string(),
+                    range: TextRange::new(21, 22),
+                },
+                ParseError {
+                    message: "Expected a statement, found `)`".to_string(),
+                    range: TextRange::new(27, 28),
+                },
+                ParseError {
+                    message: "Expected `)`".to_string(),
+                    range: TextRange::empty(37),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_keep_comments_and_invalid_text_in_the_tree() {
+        let source = "/// doc\nsevero a = \"x\" @ /* c */\n\"open";
+        let parse = parse(source);
+        assert_eq!(parse.syntax().text(), source);
+        assert!(parse.errors.is_empty());
+        let trivia: Vec<TokenType> = parse
+            .syntax()
+            .descendant_tokens()
+            .map(|token| token.kind())
+            .filter(|kind| kind.is_comment() || *kind == TokenType::Error)
+            .collect();
+        assert_eq!(
+            trivia,
+            vec![
+                TokenType::DocComment,
+                TokenType::Error,
+                TokenType::BlockComment,
+                TokenType::Error,
+            ]
+        );
+    }
+
     #[test]
     fn should_return_empty_when_a_variable_is_incomplete() {
         let tokens = vec![Token {
             token_type: TokenType::Var,
-            line: 0,
-            col: 0,
+            span: Span::default(),
             literal: None,
         }];
         let found_vars = get_vars(tokens);
@@ -71,26 +870,22 @@ mod tests {
         let tokens = vec![
             Token {
                 token_type: TokenType::Print,
-                line: 0,
-                col: 0,
+                span: Span::default(),
                 literal: None,
             },
             Token {
                 token_type: TokenType::LeftParen,
-                line: 0,
-                col: 0,
+                span: Span::default(),
                 literal: None,
             },
             Token {
                 token_type: TokenType::Var,
-                line: 0,
-                col: 0,
+                span: Span::default(),
                 literal: None,
             },
             Token {
                 token_type: TokenType::RightParen,
-                line: 0,
-                col: 0,
+                span: Span::default(),
                 literal: None,
             },
         ];
@@ -0,0 +1,421 @@
+use super::{
+    ast::{AstNode, Name},
+    syntax::{NodeKind, SyntaxNode, TextRange},
+    types::TokenType,
+};
+
+pub type ScopeId = usize;
+pub type SymbolId = usize;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+    Parameter,
+    Class,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Scope {
+    pub parent: Option<ScopeId>,
+    pub range: TextRange,
+    /// Function bodies run later than the code around them, so they may
+    /// refer to names declared further down in enclosing scopes.
+    pub is_function: bool,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The range of the declared name.
+    pub range: TextRange,
+    /// The range of the whole declaration.
+    pub declaration_range: TextRange,
+    pub scope: ScopeId,
+    /// Offset from which the name can be read, i.e. the end of its
+    /// declaration for variables.
+    pub visible_from: usize,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Reference {
+    pub name: String,
+    pub range: TextRange,
+    pub scope: ScopeId,
+    pub symbol: Option<SymbolId>,
+    /// Whether the reference is the target of an assignment.
+    pub is_write: bool,
+    pub before_declaration: bool,
+}
+
+/// The scopes of a file and what every name in it refers to.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Resolution {
+    pub scopes: Vec<Scope>,
+    pub symbols: Vec<Symbol>,
+    pub references: Vec<Reference>,
+}
+
+impl Resolution {
+    pub fn resolve(root: &SyntaxNode) -> Resolution {
+        let mut resolver = Resolver::default();
+        let scope = resolver.push_scope(None, root.text_range(), true);
+        resolver.walk_children(root, scope);
+        resolver.resolve_references();
+        resolver.resolution
+    }
+
+    pub fn references_to(&self, symbol: SymbolId) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |reference| reference.symbol == Some(symbol))
+    }
+
+    /// The innermost scope containing `offset`.
+    pub fn scope_at(&self, offset: usize) -> ScopeId {
+        self.scopes
+            .iter()
+            .enumerate()
+            .filter(|(_, scope)| scope.range.contains_inclusive(offset))
+            .min_by_key(|(_, scope)| scope.range.len())
+            .map_or(0, |(id, _)| id)
+    }
+
+    pub fn scope_chain(&self, scope: ScopeId) -> impl Iterator<Item = ScopeId> + '_ {
+        std::iter::successors(Some(scope), |scope| self.scopes[*scope].parent)
+    }
+
+    /// The symbols that can be read at `offset`, innermost first.
+    pub fn visible_symbols(&self, offset: usize) -> Vec<SymbolId> {
+        let mut visible: Vec<SymbolId> = Vec::new();
+        for scope in self.scope_chain(self.scope_at(offset)) {
+            for (id, symbol) in self.symbols.iter().enumerate().rev() {
+                let shadowed = visible
+                    .iter()
+                    .any(|other| self.symbols[*other].name == symbol.name);
+                if symbol.scope == scope && symbol.visible_from <= offset && !shadowed {
+                    visible.push(id);
+                }
+            }
+        }
+        visible
+    }
+
+    pub fn symbol_at(&self, offset: usize) -> Option<SymbolId> {
+        let declared = self
+            .symbols
+            .iter()
+            .position(|symbol| symbol.range.contains_inclusive(offset));
+        declared.or_else(|| {
+            self.references
+                .iter()
+                .find(|reference| reference.range.contains_inclusive(offset))
+                .and_then(|reference| reference.symbol)
+        })
+    }
+}
+
+#[derive(Default)]
+struct Resolver {
+    resolution: Resolution,
+}
+
+impl Resolver {
+    fn push_scope(
+        &mut self,
+        parent: Option<ScopeId>,
+        range: TextRange,
+        is_function: bool,
+    ) -> ScopeId {
+        self.resolution.scopes.push(Scope {
+            parent,
+            range,
+            is_function,
+        });
+        self.resolution.scopes.len() - 1
+    }
+
+    fn declare(
+        &mut self,
+        name: &Name,
+        kind: SymbolKind,
+        declaration: &SyntaxNode,
+        scope: ScopeId,
+        visible_from: usize,
+    ) {
+        let text = name.text();
+        if text.is_empty() {
+            return;
+        }
+        self.resolution.symbols.push(Symbol {
+            name: text,
+            kind,
+            range: name.text_range(),
+            declaration_range: declaration.text_range(),
+            scope,
+            visible_from,
+        });
+    }
+
+    fn walk_children(&mut self, node: &SyntaxNode, scope: ScopeId) {
+        for child in node.children() {
+            self.walk(&child, scope);
+        }
+    }
+
+    fn walk(&mut self, node: &SyntaxNode, scope: ScopeId) {
+        match node.kind() {
+            NodeKind::VarDecl => {
+                let is_function = node.first_child_of(NodeKind::FunExpr).is_some();
+                for child in node.children() {
+                    if child.kind() != NodeKind::Name {
+                        self.walk(&child, scope);
+                    }
+                }
+                if let Some(name) = node.first_child_of(NodeKind::Name).and_then(Name::cast) {
+                    let kind = match is_function {
+                        true => SymbolKind::Function,
+                        false => SymbolKind::Variable,
+                    };
+                    self.declare(&name, kind, node, scope, node.text_range().end);
+                }
+            }
+            NodeKind::ClassDecl => {
+                if let Some(name) = node.first_child_of(NodeKind::Name).and_then(Name::cast) {
+                    let visible_from = name.text_range().end;
+                    self.declare(&name, SymbolKind::Class, node, scope, visible_from);
+                }
+                for child in node.children() {
+                    if child.kind() != NodeKind::Name {
+                        self.walk(&child, scope);
+                    }
+                }
+            }
+            NodeKind::FunExpr | NodeKind::Method => {
+                let function_scope = self.push_scope(Some(scope), node.text_range(), true);
+                for child in node.children() {
+                    match child.kind() {
+                        NodeKind::ParamList => {
+                            for param in child.children().filter_map(Name::cast) {
+                                let visible_from = param.text_range().start;
+                                let declaration = param.syntax().clone();
+                                self.declare(
+                                    &param,
+                                    SymbolKind::Parameter,
+                                    &declaration,
+                                    function_scope,
+                                    visible_from,
+                                );
+                            }
+                        }
+                        NodeKind::Block => self.walk_children(&child, function_scope),
+                        NodeKind::Name => {}
+                        _ => self.walk(&child, function_scope),
+                    }
+                }
+            }
+            NodeKind::Block | NodeKind::ForStmt => {
+                let block_scope = self.push_scope(Some(scope), node.text_range(), false);
+                self.walk_children(node, block_scope);
+            }
+            NodeKind::NameRef => {
+                if is_property_name(node) {
+                    return;
+                }
+                let is_write = node.parent().is_some_and(|parent| {
+                    parent.kind() == NodeKind::AssignExpr
+                        && parent.children().next().as_ref() == Some(node)
+                });
+                let name = node
+                    .tokens()
+                    .find(|token| matches!(token.kind(), TokenType::Identifier | TokenType::Print))
+                    .map(|token| token.text().to_string())
+                    .unwrap_or_default();
+                self.resolution.references.push(Reference {
+                    name,
+                    range: node.text_range(),
+                    scope,
+                    symbol: None,
+                    is_write,
+                    before_declaration: false,
+                });
+            }
+            _ => self.walk_children(node, scope),
+        }
+    }
+
+    fn resolve_references(&mut self) {
+        let resolution = &self.resolution;
+        let resolved: Vec<(Option<SymbolId>, bool)> = resolution
+            .references
+            .iter()
+            .map(|reference| resolve_reference(resolution, reference))
+            .collect();
+        for (reference, (symbol, before_declaration)) in
+            self.resolution.references.iter_mut().zip(resolved)
+        {
+            reference.symbol = symbol;
+            reference.before_declaration = before_declaration;
+        }
+    }
+}
+
+fn resolve_reference(resolution: &Resolution, reference: &Reference) -> (Option<SymbolId>, bool) {
+    let offset = reference.range.start;
+    let mut crossed_function = false;
+    for scope in resolution.scope_chain(reference.scope) {
+        let candidates: Vec<(SymbolId, &Symbol)> = resolution
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| symbol.scope == scope && symbol.name == reference.name)
+            .collect();
+        let visible = candidates
+            .iter()
+            .filter(|(_, symbol)| symbol.visible_from <= offset)
+            .max_by_key(|(_, symbol)| symbol.visible_from);
+        if let Some((id, _)) = visible {
+            return (Some(*id), false);
+        }
+        if let Some((id, _)) = candidates.first() {
+            return (Some(*id), !crossed_function);
+        }
+        crossed_function |= resolution.scopes[scope].is_function;
+    }
+    (None, false)
+}
+
+/// Whether `name_ref` names a property, as in `a.name` or `super.name`,
+/// instead of a variable.
+fn is_property_name(name_ref: &SyntaxNode) -> bool {
+    let Some(parent) = name_ref.parent() else {
+        return false;
+    };
+    match parent.kind() {
+        NodeKind::SuperExpr => true,
+        NodeKind::FieldExpr => parent
+            .first_token_of(TokenType::Dot)
+            .is_some_and(|dot| name_ref.text_range().start >= dot.text_range().end),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::parser::parse;
+
+    use super::*;
+
+    fn resolve(source: &str) -> Resolution {
+        Resolution::resolve(&parse(source).syntax())
+    }
+
+    fn resolved_name(
+        resolution: &Resolution,
+        reference: &Reference,
+    ) -> Option<(SymbolKind, TextRange)> {
+        reference.symbol.map(|symbol| {
+            (
+                resolution.symbols[symbol].kind,
+                resolution.symbols[symbol].range,
+            )
+        })
+    }
+
+    #[test]
+    fn should_resolve_block_scopes() {
+        let source = "severo a = 1\n{ severo a = 2\nprint(a) }\nprint(a)";
+        let resolution = resolve(source);
+        assert_eq!(resolution.symbols.len(), 2);
+        let references: Vec<_> = resolution
+            .references
+            .iter()
+            .filter(|reference| reference.name == "a")
+            .map(|reference| resolved_name(&resolution, reference).unwrap().1)
+            .collect();
+        assert_eq!(
+            references,
+            vec![TextRange::new(22, 23), TextRange::new(7, 8)]
+        );
+    }
+
+    #[test]
+    fn should_leave_builtins_and_unknown_names_unresolved() {
+        let resolution = resolve("print(missing)");
+        assert!(resolution
+            .references
+            .iter()
+            .all(|reference| reference.symbol.is_none()));
+        assert_eq!(resolution.references.len(), 2);
+    }
+
+    #[test]
+    fn should_resolve_parameters_and_recursion() {
+        let source = "severo fact = fun(n) { return fact(n) }";
+        let resolution = resolve(source);
+        let kinds: Vec<_> = resolution
+            .references
+            .iter()
+            .map(|reference| {
+                assert!(!reference.before_declaration);
+                resolved_name(&resolution, reference).unwrap().0
+            })
+            .collect();
+        assert_eq!(kinds, vec![SymbolKind::Function, SymbolKind::Parameter]);
+    }
+
+    #[test]
+    fn should_flag_reads_before_declaration() {
+        let resolution = resolve("print(a)\nsevero a = a");
+        let flags: Vec<_> = resolution
+            .references
+            .iter()
+            .filter(|reference| reference.name == "a")
+            .map(|reference| (reference.symbol, reference.before_declaration))
+            .collect();
+        assert_eq!(flags, vec![(Some(0), true), (Some(0), true)]);
+    }
+
+    #[test]
+    fn should_skip_property_names_and_mark_writes() {
+        let source = "class A < B { m() { this.x = super.y\nx = 1 } }";
+        let resolution = resolve(source);
+        let names: Vec<_> = resolution
+            .references
+            .iter()
+            .map(|reference| (reference.name.as_str(), reference.is_write))
+            .collect();
+        assert_eq!(names, vec![("B", false), ("x", true)]);
+        assert_eq!(resolution.symbols[0].kind, SymbolKind::Class);
+    }
+
+    #[test]
+    fn should_scope_for_loop_variables() {
+        let source = "for (severo i = 0; i < 3; i = i + 1) { print(i) }\nprint(i)";
+        let resolution = resolve(source);
+        let last = resolution.references.last().unwrap();
+        assert_eq!(last.name, "i");
+        assert_eq!(last.symbol, None);
+        assert_eq!(
+            resolution
+                .references
+                .iter()
+                .filter(|reference| reference.symbol == Some(0))
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn should_list_visible_symbols() {
+        let source = "severo a = 1\nsevero b = 2\n{ severo a = 3\n  }";
+        let resolution = resolve(source);
+        let names: Vec<_> = resolution
+            .visible_symbols(source.len() - 2)
+            .into_iter()
+            .map(|symbol| resolution.symbols[symbol].range.start)
+            .collect();
+        assert_eq!(names, vec![35, 20]);
+    }
+}
@@ -1,261 +1,328 @@
 use super::{
-    helpers::{invert_escape, is_alpha, is_alphanumeric, is_numeric, rest_matches},
+    helpers::{is_alpha, is_alphanumeric, is_numeric, rest_matches, unescape},
     keywords::get_keywords_hash,
-    types::{Literal, ScanError, ScanResult, Token, TokenType},
+    types::{Literal, Location, ScanError, ScanResult, Span, Token, TokenType},
 };
 
 pub fn scan_tokens(source: String) -> ScanResult {
-    let mut tokens: Vec<Token> = Vec::new();
-    for (line_count, line) in source.clone().lines().enumerate() {
-        let line_tokens = scan_tokens_in_line(line.to_string(), line_count);
-        tokens.extend(line_tokens);
-    }
-    tokens.push(Token {
-        token_type: TokenType::Eof,
-        line: 0,
-        col: 0,
-        literal: None,
-    });
-    ScanResult { tokens }
+    let mut scanner = Scanner::new(source.as_str(), false);
+    scanner.scan();
+    ScanResult {
+        tokens: scanner.tokens,
+        errors: scanner.errors,
+    }
+}
+
+/// Like `scan_tokens`, but keeps whitespace, newlines and unrecognized text as
+/// tokens, so that concatenating the token texts gives back the source.
+pub fn scan_tokens_lossless(source: String) -> ScanResult {
+    let mut scanner = Scanner::new(source.as_str(), true);
+    scanner.scan();
+    ScanResult {
+        tokens: scanner.tokens,
+        errors: scanner.errors,
+    }
 }
 
-fn scan_tokens_in_line(line: String, line_count: usize) -> Vec<Token> {
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut index = 0;
-    let end_of_line = line.len();
-    while index < end_of_line {
-        if line.clone().get(index..index + 1).is_none() {
-            break;
+/// Moves `location` past `char`. `\r\n` counts as a single line break, so the
+/// `\r` only advances the offset and the `\n` starts the new line.
+pub fn advance_location(location: &mut Location, char: char, next_char: Option<char>) {
+    location.offset += char.len_utf8();
+    match char {
+        '\r' if next_char == Some('\n') => {}
+        '\n' | '\r' => {
+            location.line += 1;
+            location.col = 0;
         }
-        match line.clone().get(index..index + 1).unwrap() {
-            "\r" | "\t" | " " | "\n" | "\0" => {
-                index += 1;
-                continue;
-            }
-            "(" => {
-                tokens.push(Token {
-                    token_type: TokenType::LeftParen,
-                    line: line_count,
-                    col: index,
-                    literal: None,
-                });
-                index += 1;
-                continue;
-            }
-            ")" => {
-                tokens.push(Token {
-                    token_type: TokenType::RightParen,
-                    line: line_count,
-                    col: index,
-                    literal: None,
-                });
-                index += 1;
-                continue;
-            }
-            "=" => match rest_matches(index, line.as_str(), "=") {
-                true => {
-                    index += 1;
-                    continue;
+        _ => location.col += char.len_utf16(),
+    }
+}
+
+struct Scanner<'a> {
+    source: &'a str,
+    lossless: bool,
+    start: Location,
+    current: Location,
+    tokens: Vec<Token>,
+    errors: Vec<ScanError>,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(source: &'a str, lossless: bool) -> Self {
+        Scanner {
+            source,
+            lossless,
+            start: Location::default(),
+            current: Location::default(),
+            tokens: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn scan(&mut self) {
+        while let Some(char) = self.peek() {
+            self.start = self.current;
+            match char {
+                '\t' | ' ' | '\0' => self.whitespace(),
+                '\r' | '\n' => {
+                    if self.advance() == Some('\r') && self.peek() == Some('\n') {
+                        self.advance();
+                    }
+                    self.push_trivia(TokenType::Newline);
                 }
-                false => {
-                    tokens.push(Token {
-                        token_type: TokenType::Equal,
-                        line: line_count,
-                        col: index,
-                        literal: None,
+                '(' => self.single(TokenType::LeftParen),
+                ')' => self.single(TokenType::RightParen),
+                '{' => self.single(TokenType::LeftBrace),
+                '}' => self.single(TokenType::RightBrace),
+                ',' => self.single(TokenType::Comma),
+                '.' => self.single(TokenType::Dot),
+                ';' => self.single(TokenType::Semicolon),
+                '+' => self.single(TokenType::Plus),
+                '-' => self.single(TokenType::Minus),
+                '*' => self.single(TokenType::Star),
+                '=' => self.one_or_two('=', TokenType::Equal, TokenType::EqualEqual),
+                '!' => self.one_or_two('=', TokenType::Bang, TokenType::BangEqual),
+                '<' => self.one_or_two('=', TokenType::Less, TokenType::LessEqual),
+                '>' => self.one_or_two('=', TokenType::Greater, TokenType::GreaterEqual),
+                '/' => self.slash(),
+                '"' => self.string(),
+                char if is_numeric(char) => self.number(),
+                char if is_alpha(char) => self.identifier(),
+                char => {
+                    self.advance();
+                    self.errors.push(ScanError {
+                        message: format!("Unexpected character `{}`", char),
+                        span: self.span(),
                     });
-                    index += 1;
-                    continue;
-                }
-            },
-            "\"" => match string(line.clone(), line_count, index) {
-                Ok((token, skip)) => {
-                    tokens.push(token);
-                    index += skip;
-                    continue;
-                }
-                Err(_) => {
-                    index += 1;
-                    continue;
-                }
-            },
-            char => {
-                if is_numeric(char) {
-                    match number(line.clone(), line_count, index) {
-                        Ok((token, skip)) => {
-                            tokens.push(token);
-                            index += skip;
-                            continue;
-                        }
-                        Err(_) => {
-                            index += 1;
-                            continue;
-                        }
-                    }
-                } else if is_alpha(char) {
-                    let (token, skip) = identifier(line.clone(), line_count, index);
-                    tokens.push(token);
-                    index += skip;
-                    continue;
-                } else {
-                    index += 1;
-                    continue;
+                    self.push_trivia(TokenType::Error);
                 }
             }
-        };
+        }
+        self.start = self.current;
+        self.push(TokenType::Eof, None);
     }
-    tokens
-}
 
-fn string(
-    line: String,
-    line_count: usize,
-    start_param: usize,
-) -> Result<(Token, usize), ScanError> {
-    let start = start_param + 1;
-    let mut current_index = start;
-    loop {
-        let char = line_range(line.clone(), current_index, current_index + 1);
-        if char == "\n" || char == "\0" || char == "\"" {
-            break;
-        } else {
-            current_index += 1;
-            continue;
+    fn peek(&self) -> Option<char> {
+        self.source[self.current.offset..].chars().next()
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.source[self.current.offset..].chars().nth(1)
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        let next_char = self.peek_next();
+        advance_location(&mut self.current, char, next_char);
+        Some(char)
+    }
+
+    fn text(&self) -> &'a str {
+        &self.source[self.start.offset..self.current.offset]
+    }
+
+    fn span(&self) -> Span {
+        Span {
+            start: self.start,
+            end: self.current,
         }
     }
-    let char = line.get(current_index..current_index + 1).unwrap();
-    if char == "\"" {
-        let literal_str = invert_escape(line.get(start..current_index).unwrap().to_string());
-        let literal = Literal::String(literal_str);
-        Ok((
-            Token {
-                token_type: TokenType::String,
-                line: line_count,
-                col: start,
-                literal: Some(literal),
-            },
-            current_index + 2 - start,
-        ))
-    } else if char == "\n" || char == "\0" {
-        Err(ScanError {
-            message: "Unterminated string".to_string(),
-            col: start,
-        })
-    } else {
-        Err(ScanError {
-            message: format!("Expected a `\"` instead `{}`", char),
-            col: start,
-        })
+
+    fn push(&mut self, token_type: TokenType, literal: Option<Literal>) {
+        self.tokens.push(Token {
+            token_type,
+            span: self.span(),
+            literal,
+        });
+    }
+
+    fn push_trivia(&mut self, token_type: TokenType) {
+        if self.lossless {
+            self.push(token_type, None);
+        }
+    }
+
+    fn single(&mut self, token_type: TokenType) {
+        self.advance();
+        self.push(token_type, None);
+    }
+
+    fn one_or_two(&mut self, second: char, one: TokenType, two: TokenType) {
+        self.advance();
+        match self.peek() == Some(second) {
+            true => {
+                self.advance();
+                self.push(two, None);
+            }
+            false => self.push(one, None),
+        }
     }
-}
 
-fn identifier(line: String, line_count: usize, start_param: usize) -> (Token, usize) {
-    let start = start_param;
-    let mut current_index = start;
-    loop {
-        if current_index >= line.len() {
-            break;
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some('\t' | ' ' | '\0')) {
+            self.advance();
         }
-        let char = line_range(line.clone(), current_index, current_index + 1);
-        if is_alphanumeric(char.as_str()) {
-            current_index += 1;
-            continue;
+        self.push_trivia(TokenType::Whitespace);
+    }
+
+    fn slash(&mut self) {
+        if rest_matches(self.current.offset, self.source, "//") {
+            self.comment_line();
+        } else if rest_matches(self.current.offset, self.source, "/*") {
+            self.comment_block();
         } else {
-            break;
+            self.single(TokenType::Slash);
         }
     }
-    let text = line.get(start..current_index).unwrap();
-    let binding = get_keywords_hash();
-    let token_type = binding.get(text);
-    match token_type {
-        Some(type_value) => (
-            Token {
-                token_type: type_value.clone(),
-                line: line_count,
-                col: start,
-                literal: None,
-            },
-            current_index - start,
-        ),
-        None => (
-            Token {
-                token_type: TokenType::Identifier,
-                literal: Some(Literal::Identifier(text.to_string())),
-                line: line_count,
-                col: start,
-            },
-            current_index - start,
-        ),
+
+    fn comment_line(&mut self) {
+        while !matches!(self.peek(), None | Some('\n') | Some('\r')) {
+            self.advance();
+        }
+        let text = &self.text()[2..];
+        let is_doc = text.starts_with('/') && !text.starts_with("//");
+        match is_doc {
+            true => {
+                let doc = &text[1..];
+                let doc = doc.strip_prefix(' ').unwrap_or(doc).to_string();
+                self.push(TokenType::DocComment, Some(Literal::Comment(doc)));
+            }
+            false => {
+                let comment = text.to_string();
+                self.push(TokenType::LineComment, Some(Literal::Comment(comment)));
+            }
+        }
     }
-}
 
-fn number(
-    line: String,
-    line_count: usize,
-    start_param: usize,
-) -> Result<(Token, usize), ScanError> {
-    let start = start_param;
-    let mut current_index = start;
-    loop {
-        if current_index >= line.len() {
-            break;
+    fn comment_block(&mut self) {
+        self.advance();
+        self.advance();
+        let mut depth = 1;
+        while depth > 0 {
+            if rest_matches(self.current.offset, self.source, "/*") {
+                self.advance();
+                self.advance();
+                depth += 1;
+            } else if rest_matches(self.current.offset, self.source, "*/") {
+                self.advance();
+                self.advance();
+                depth -= 1;
+            } else if self.advance().is_none() {
+                let mut opening_end = self.start;
+                opening_end.offset += 2;
+                opening_end.col += 2;
+                self.errors.push(ScanError {
+                    message: "Unterminated block comment".to_string(),
+                    span: Span {
+                        start: self.start,
+                        end: opening_end,
+                    },
+                });
+                break;
+            }
         }
-        let char = line_range(line.clone(), current_index, current_index + 1);
-        if is_numeric(char.as_str()) {
-            current_index += 1;
-            continue;
-        } else {
-            break;
+        let comment = self.text().to_string();
+        self.push(TokenType::BlockComment, Some(Literal::Comment(comment)));
+    }
+
+    fn string(&mut self) {
+        self.advance();
+        let body_start = self.current;
+        loop {
+            match self.advance() {
+                Some('\\') => {
+                    self.advance();
+                }
+                Some('"') => break,
+                Some(_) => {}
+                None => {
+                    // Strings may span lines, but an unterminated one only
+                    // takes its own line, so the rest of the file still scans.
+                    self.current = body_start;
+                    while !matches!(self.peek(), None | Some('\n') | Some('\r')) {
+                        self.advance();
+                    }
+                    self.errors.push(ScanError {
+                        message: "Unterminated string".to_string(),
+                        span: self.span(),
+                    });
+                    self.push_trivia(TokenType::Error);
+                    return;
+                }
+            }
+        }
+
+        let text = self.text();
+        let body = &text[1..text.len() - 1];
+        let (literal_str, escape_errors) = unescape(body);
+        let mut body_start = self.start;
+        advance_location(&mut body_start, '"', body.chars().next());
+        for error in escape_errors {
+            let start = location_in(body, body_start, error.start);
+            let end = location_in(body, body_start, error.end);
+            self.errors.push(ScanError {
+                message: error.message,
+                span: Span { start, end },
+            });
         }
+        self.push(TokenType::String, Some(Literal::String(literal_str)));
     }
 
-    let char = line_range(line.clone(), current_index - 1, current_index);
-    let prev_char = line_range(line.clone(), current_index - 2, current_index - 1);
-    let next_char = match current_index < line.len() {
-        true => line_range(line.clone(), current_index + 1, current_index + 2),
-        false => '\0'.to_string(),
-    };
-    let incorrect_float_number = is_numeric(prev_char.as_str())
-        && char == "."
-        && (next_char == "\0" || is_alpha(next_char.as_str()));
-
-    if incorrect_float_number || is_alpha(char.as_str()) {
-        Err(ScanError {
-            message: format!("Expected a `number` instead `{}`", next_char),
-            col: current_index + 2,
-        })
-    } else {
-        let line = line.get(start..current_index).unwrap();
-        let the_number: f64 = line.parse().unwrap();
-        let literal = Literal::Number(the_number);
-        Ok((
-            Token {
-                token_type: TokenType::Number,
-                line: line_count,
-                col: start,
-                literal: Some(literal),
-            },
-            current_index - start,
-        ))
+    fn identifier(&mut self) {
+        while self.peek().is_some_and(is_alphanumeric) {
+            self.advance();
+        }
+        let text = self.text();
+        match get_keywords_hash().get(text) {
+            Some(token_type) => self.push(*token_type, None),
+            None => self.push(
+                TokenType::Identifier,
+                Some(Literal::Identifier(text.to_string())),
+            ),
+        }
+    }
+
+    fn number(&mut self) {
+        while self.peek().is_some_and(is_numeric) {
+            self.advance();
+        }
+        if self.peek() == Some('.') && self.peek_next().is_some_and(is_numeric) {
+            self.advance();
+            while self.peek().is_some_and(is_numeric) {
+                self.advance();
+            }
+        }
+        let the_number: f64 = self.text().parse().unwrap();
+        self.push(TokenType::Number, Some(Literal::Number(the_number)));
     }
 }
 
-fn line_range(line: String, start: usize, end: usize) -> String {
-    let line_as_vec_chars = line.chars().collect::<Vec<_>>();
-    let line_string_slice = line_as_vec_chars[start..end]
-        .iter()
-        .cloned()
-        .collect::<String>();
-    line_string_slice
+fn location_in(text: &str, start: Location, offset: usize) -> Location {
+    let mut location = start;
+    let mut chars = text[..offset].chars().peekable();
+    while let Some(char) = chars.next() {
+        let next_char = chars
+            .peek()
+            .copied()
+            .or_else(|| text[offset..].chars().next());
+        advance_location(&mut location, char, next_char);
+    }
+    location
 }
 
 #[cfg(test)]
 mod tests {
+    use tower_lsp::lsp_types::{Position, Range};
+
     use crate::spec::{
-        scanner::scan_tokens,
-        types::{Literal, TokenType},
+        scanner::{scan_tokens, scan_tokens_lossless},
+        types::{Literal, Location, ScanError, Span, TokenType},
     };
 
+    fn location(offset: usize, line: usize, col: usize) -> Location {
+        Location { offset, line, col }
+    }
+
     #[test]
     fn should_identify_a_print() {
         let input = r#"
@@ -297,6 +364,287 @@ mod tests {
         assert_eq!(tokens[0].literal, Some(Literal::Number(10_f64)));
     }
 
+    #[test]
+    fn should_identify_a_decimal_number() {
+        let result = scan_tokens("3.25".to_string());
+        assert_eq!(result.tokens[0].literal, Some(Literal::Number(3.25)));
+        assert_eq!(result.tokens[0].span.end, location(4, 0, 4));
+    }
+
+    #[test]
+    fn should_identify_a_string_with_escaped_quotes() {
+        let input = r#"print("say \"hi\" \\n")"#;
+        let result = scan_tokens(input.to_string());
+        let tokens = result.tokens;
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(
+            tokens[2].literal,
+            Some(Literal::String("say \"hi\" \\n".to_string()))
+        );
+        assert_eq!(tokens[3].token_type, TokenType::RightParen);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn should_report_invalid_escapes_with_their_columns() {
+        let input = r#"severo a = "x\qy\u{110000}""#;
+        let result = scan_tokens(input.to_string());
+        assert_eq!(
+            result.errors,
+            vec![
+                ScanError {
+                    message: "Unknown escape sequence `\\q`".to_string(),
+                    span: Span {
+                        start: location(13, 0, 13),
+                        end: location(15, 0, 15),
+                    },
+                },
+                ScanError {
+                    message: "Invalid unicode escape `\\u{110000}`".to_string(),
+                    span: Span {
+                        start: location(16, 0, 16),
+                        end: location(26, 0, 26),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_identify_a_multi_line_string() {
+        let input = "severo a = \"one\ntwo \\q\"\nprint(a)";
+        let result = scan_tokens(input.to_string());
+        let tokens = result.tokens;
+        assert_eq!(
+            tokens[3].literal,
+            Some(Literal::String("one\ntwo \\q".to_string()))
+        );
+        assert_eq!(tokens[3].span.start, location(11, 0, 11));
+        assert_eq!(tokens[3].span.end, location(23, 1, 7));
+        assert_eq!(tokens[4].token_type, TokenType::Print);
+        assert_eq!(
+            result.errors[0].span,
+            Span {
+                start: location(20, 1, 4),
+                end: location(22, 1, 6),
+            }
+        );
+    }
+
+    #[test]
+    fn should_report_an_unterminated_string() {
+        let input = "print(\"Hello)\nsevero a";
+        let result = scan_tokens(input.to_string());
+        assert_eq!(
+            result.errors,
+            vec![ScanError {
+                message: "Unterminated string".to_string(),
+                span: Span {
+                    start: location(6, 0, 6),
+                    end: location(13, 0, 13),
+                },
+            }]
+        );
+        assert_eq!(result.tokens[2].token_type, TokenType::Var);
+    }
+
+    #[test]
+    fn should_identify_comments() {
+        let input = r#"
+            // severo hidden
+            /// Documented value
+            severo shown // trailing
+        "#;
+        let result = scan_tokens(input.to_string());
+        let tokens = result.tokens;
+        assert_eq!(tokens.len(), 6);
+        assert_eq!(tokens[0].token_type, TokenType::LineComment);
+        assert_eq!(
+            tokens[0].literal,
+            Some(Literal::Comment(" severo hidden".to_string()))
+        );
+        assert_eq!(tokens[1].token_type, TokenType::DocComment);
+        assert_eq!(
+            tokens[1].literal,
+            Some(Literal::Comment("Documented value".to_string()))
+        );
+        assert_eq!(tokens[2].token_type, TokenType::Var);
+        assert_eq!(tokens[3].token_type, TokenType::Identifier);
+        assert_eq!(tokens[4].token_type, TokenType::LineComment);
+    }
+
+    #[test]
+    fn should_identify_nested_block_comments_across_lines() {
+        let input = "severo a /* outer /* inner */\nsevero hidden */ print";
+        let result = scan_tokens(input.to_string());
+        let tokens = result.tokens;
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0].token_type, TokenType::Var);
+        assert_eq!(tokens[1].token_type, TokenType::Identifier);
+        assert_eq!(tokens[2].token_type, TokenType::BlockComment);
+        assert_eq!(
+            tokens[2].literal,
+            Some(Literal::Comment(
+                "/* outer /* inner */\nsevero hidden */".to_string()
+            ))
+        );
+        assert_eq!(
+            tokens[2].span,
+            Span {
+                start: location(9, 0, 9),
+                end: location(46, 1, 16),
+            }
+        );
+        assert_eq!(tokens[3].token_type, TokenType::Print);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn should_report_an_unterminated_block_comment() {
+        let input = "print /* open /* */";
+        let result = scan_tokens(input.to_string());
+        assert_eq!(
+            result.errors,
+            vec![ScanError {
+                message: "Unterminated block comment".to_string(),
+                span: Span {
+                    start: location(6, 0, 6),
+                    end: location(8, 0, 8),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn should_identify_a_number_followed_by_a_single_char() {
+        let result = scan_tokens("10 ".to_string());
+        assert_eq!(result.tokens[0].literal, Some(Literal::Number(10_f64)));
+    }
+
+    #[test]
+    fn should_track_start_and_end_of_tokens() {
+        let input = "severo a = 1\r\nprint(a)\r\n";
+        let tokens = scan_tokens(input.to_string()).tokens;
+        assert_eq!(tokens.len(), 9);
+        assert_eq!(tokens[2].token_type, TokenType::Equal);
+        assert_eq!(
+            tokens[4].span,
+            Span {
+                start: location(14, 1, 0),
+                end: location(19, 1, 5),
+            }
+        );
+        assert_eq!(
+            tokens[8].span,
+            Span {
+                start: location(24, 2, 0),
+                end: location(24, 2, 0),
+            }
+        );
+    }
+
+    #[test]
+    fn should_count_columns_in_utf16_code_units() {
+        let input = "\"😀é\" severo";
+        let tokens = scan_tokens(input.to_string()).tokens;
+        assert_eq!(tokens[0].span.end, location(8, 0, 5));
+        assert_eq!(tokens[1].token_type, TokenType::Var);
+        assert_eq!(tokens[1].span.start, location(9, 0, 6));
+    }
+
+    #[test]
+    fn should_convert_a_span_into_a_range() {
+        let tokens = scan_tokens("\n  print".to_string()).tokens;
+        let range: Range = tokens[0].span.into();
+        assert_eq!(
+            range,
+            Range {
+                start: Position {
+                    line: 1,
+                    character: 2
+                },
+                end: Position {
+                    line: 1,
+                    character: 7
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn should_identify_operators_and_punctuation() {
+        let input = "{ } , . ; + - * / ! != = == < <= > >=";
+        let token_types: Vec<TokenType> = scan_tokens(input.to_string())
+            .tokens
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::LeftBrace,
+                TokenType::RightBrace,
+                TokenType::Comma,
+                TokenType::Dot,
+                TokenType::Semicolon,
+                TokenType::Plus,
+                TokenType::Minus,
+                TokenType::Star,
+                TokenType::Slash,
+                TokenType::Bang,
+                TokenType::BangEqual,
+                TokenType::Equal,
+                TokenType::EqualEqual,
+                TokenType::Less,
+                TokenType::LessEqual,
+                TokenType::Greater,
+                TokenType::GreaterEqual,
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_unexpected_characters() {
+        let result = scan_tokens("severo a = @".to_string());
+        assert_eq!(result.errors[0].message, "Unexpected character `@`");
+        assert_eq!(result.tokens.len(), 4);
+    }
+
+    #[test]
+    fn should_keep_every_char_when_lossless() {
+        let input = "severo a = \"b\" @ // c\r\n\tprint(a)\n\"open";
+        let result = scan_tokens_lossless(input.to_string());
+        let text: String = result
+            .tokens
+            .iter()
+            .map(|token| &input[token.span.start.offset..token.span.end.offset])
+            .collect();
+        assert_eq!(text, input);
+        let trivia: Vec<TokenType> = result
+            .tokens
+            .into_iter()
+            .map(|token| token.token_type)
+            .filter(|token_type| token_type.is_trivia())
+            .collect();
+        assert_eq!(
+            trivia,
+            vec![
+                TokenType::Whitespace,
+                TokenType::Whitespace,
+                TokenType::Whitespace,
+                TokenType::Whitespace,
+                TokenType::Error,
+                TokenType::Whitespace,
+                TokenType::LineComment,
+                TokenType::Newline,
+                TokenType::Whitespace,
+                TokenType::Newline,
+                TokenType::Error,
+            ]
+        );
+    }
+
     #[test]
     fn should_identify_keywords() {
         let input = r#"
@@ -0,0 +1,634 @@
+use std::{fmt, rc::Rc};
+
+use super::types::{Literal, Token, TokenType};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum NodeKind {
+    SourceFile,
+    VarDecl,
+    ClassDecl,
+    Method,
+    ExprStmt,
+    IfStmt,
+    ElifBranch,
+    ElseBranch,
+    WhileStmt,
+    ForStmt,
+    ReturnStmt,
+    Block,
+    Name,
+    NameRef,
+    ParamList,
+    ArgList,
+    Literal,
+    BinaryExpr,
+    PrefixExpr,
+    AssignExpr,
+    CallExpr,
+    FieldExpr,
+    ParenExpr,
+    FunExpr,
+    ThisExpr,
+    SuperExpr,
+    Error,
+}
+
+/// A half-open range of byte offsets in the source.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TextRange {
+    pub fn new(start: usize, end: usize) -> Self {
+        TextRange { start, end }
+    }
+
+    pub fn empty(offset: usize) -> Self {
+        TextRange::new(offset, offset)
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    pub fn contains_inclusive(&self, offset: usize) -> bool {
+        self.start <= offset && offset <= self.end
+    }
+
+    pub fn contains_range(&self, other: TextRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn cover(&self, other: TextRange) -> TextRange {
+        TextRange::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct GreenNode {
+    kind: NodeKind,
+    text_len: usize,
+    children: Vec<GreenElement>,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct GreenToken {
+    kind: TokenType,
+    text: String,
+    literal: Option<Literal>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
+
+impl GreenNode {
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+}
+
+/// Builds a green tree bottom up. Nodes can be opened retroactively with
+/// `checkpoint` and `start_node_at`, which is how left-recursive expressions
+/// get wrapped after their left-hand side was parsed.
+#[derive(Default)]
+pub struct TreeBuilder {
+    parents: Vec<(NodeKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+impl TreeBuilder {
+    pub fn start_node(&mut self, kind: NodeKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    pub fn checkpoint(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn start_node_at(&mut self, checkpoint: usize, kind: NodeKind) {
+        self.parents.push((kind, checkpoint));
+    }
+
+    pub fn token(&mut self, token: &Token, text: &str) {
+        self.children.push(GreenElement::Token(Rc::new(GreenToken {
+            kind: token.token_type,
+            text: text.to_string(),
+            literal: token.literal.clone(),
+        })));
+    }
+
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().unwrap();
+        let children: Vec<GreenElement> = self.children.drain(first_child..).collect();
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+        self.children.push(GreenElement::Node(Rc::new(GreenNode {
+            kind,
+            text_len,
+            children,
+        })));
+    }
+
+    pub fn last_node_kind(&self) -> Option<NodeKind> {
+        match self.children.last() {
+            Some(GreenElement::Node(node)) => Some(node.kind),
+            _ => None,
+        }
+    }
+
+    pub fn finish(mut self) -> Rc<GreenNode> {
+        match self.children.pop() {
+            Some(GreenElement::Node(node)) => node,
+            _ => panic!("the tree must have a root node"),
+        }
+    }
+}
+
+/// A node of the tree with its position: a cheap handle over the green node
+/// that knows its parent and absolute offset.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    offset: usize,
+    index: usize,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+    index: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl Eq for SyntaxToken {}
+
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = self.text_range();
+        write!(f, "{:?}@{}..{}", self.kind(), range.start, range.end)
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = self.text_range();
+        write!(
+            f,
+            "{:?}@{}..{} {:?}",
+            self.kind(),
+            range.start,
+            range.end,
+            self.text()
+        )
+    }
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+            index: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.green.kind
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        TextRange::new(self.0.offset, self.0.offset + self.0.green.text_len)
+    }
+
+    pub fn text(&self) -> String {
+        self.descendants_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Token(token) => Some(token.green.text.clone()),
+                SyntaxElement::Node(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// The node itself followed by its parents up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut elements = Vec::with_capacity(self.0.green.children.len());
+        for index in 0..self.0.green.children.len() {
+            let element = self.child(index, offset).expect("a child");
+            offset = element.text_range().end;
+            elements.push(element);
+        }
+        elements
+    }
+
+    /// The child at `index`, whose text starts at `offset`. Building a single
+    /// child keeps walking through siblings linear.
+    fn child(&self, index: usize, offset: usize) -> Option<SyntaxElement> {
+        let element = match self.0.green.children.get(index)? {
+            GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                green: green.clone(),
+                parent: Some(self.clone()),
+                offset,
+                index,
+            }))),
+            GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                green: green.clone(),
+                parent: self.clone(),
+                offset,
+                index,
+            }),
+        };
+        Some(element)
+    }
+
+    fn first_child_or_token(&self) -> Option<SyntaxElement> {
+        self.child(0, self.0.offset)
+    }
+
+    fn last_child_or_token(&self) -> Option<SyntaxElement> {
+        let index = self.0.green.children.len().checked_sub(1)?;
+        let len = self.0.green.children[index].text_len();
+        self.child(index, self.text_range().end - len)
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_token)
+    }
+
+    pub fn first_token_of(&self, kind: TokenType) -> Option<SyntaxToken> {
+        self.tokens().find(|token| token.kind() == kind)
+    }
+
+    pub fn first_child_of(&self, kind: NodeKind) -> Option<SyntaxNode> {
+        self.children().find(|child| child.kind() == kind)
+    }
+
+    /// Every element below this node in preorder, starting with the node.
+    pub fn descendants_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut elements = Vec::new();
+        let mut stack = vec![SyntaxElement::Node(self.clone())];
+        while let Some(element) = stack.pop() {
+            if let SyntaxElement::Node(node) = &element {
+                stack.extend(node.children_with_tokens().into_iter().rev());
+            }
+            elements.push(element);
+        }
+        elements
+    }
+
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.descendants_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    pub fn descendant_tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.descendants_with_tokens()
+            .into_iter()
+            .filter_map(SyntaxElement::into_token)
+    }
+
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        let mut element = self.first_child_or_token();
+        while let Some(current) = element {
+            match current {
+                SyntaxElement::Token(token) => return Some(token),
+                SyntaxElement::Node(node) => {
+                    if let Some(token) = node.first_token() {
+                        return Some(token);
+                    }
+                    element = SyntaxElement::Node(node).next_sibling_or_token();
+                }
+            }
+        }
+        None
+    }
+
+    pub fn last_token(&self) -> Option<SyntaxToken> {
+        let mut element = self.last_child_or_token();
+        while let Some(current) = element {
+            match current {
+                SyntaxElement::Token(token) => return Some(token),
+                SyntaxElement::Node(node) => {
+                    if let Some(token) = node.last_token() {
+                        return Some(token);
+                    }
+                    element = SyntaxElement::Node(node).prev_sibling_or_token();
+                }
+            }
+        }
+        None
+    }
+
+    pub fn next_sibling(&self) -> Option<SyntaxNode> {
+        std::iter::successors(
+            SyntaxElement::Node(self.clone()).next_sibling_or_token(),
+            SyntaxElement::next_sibling_or_token,
+        )
+        .find_map(SyntaxElement::into_node)
+    }
+
+    pub fn prev_sibling(&self) -> Option<SyntaxNode> {
+        std::iter::successors(
+            SyntaxElement::Node(self.clone()).prev_sibling_or_token(),
+            SyntaxElement::prev_sibling_or_token,
+        )
+        .find_map(SyntaxElement::into_node)
+    }
+
+    /// The token under `offset`. When `offset` sits between two tokens, the
+    /// one that is not trivia wins, preferring the token on the right.
+    pub fn token_at_offset(&self, offset: usize) -> Option<SyntaxToken> {
+        let mut candidates = self.descendant_tokens().filter(|token| {
+            let range = token.text_range();
+            range.contains(offset) || range.end == offset
+        });
+        let left = candidates.next();
+        let right = candidates.next();
+        match (left, right) {
+            (Some(left), Some(right)) if right.kind().is_trivia() => Some(left),
+            (Some(_), Some(right)) => Some(right),
+            (left, _) => left,
+        }
+    }
+
+    /// The smallest element whose range contains `range`.
+    pub fn covering_element(&self, range: TextRange) -> SyntaxElement {
+        let mut current = SyntaxElement::Node(self.clone());
+        while let SyntaxElement::Node(node) = &current {
+            let child = node
+                .children_with_tokens()
+                .into_iter()
+                .find(|child| child.text_range().contains_range(range) && !range.is_empty())
+                .or_else(|| {
+                    node.children_with_tokens()
+                        .into_iter()
+                        .find(|child| child.text_range().contains(range.start))
+                        .filter(|_| range.is_empty())
+                });
+            match child {
+                Some(child) => current = child,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// An indented dump of the tree, handy in tests.
+    pub fn debug_tree(&self) -> String {
+        let mut result = String::new();
+        let mut stack = vec![(SyntaxElement::Node(self.clone()), 0)];
+        while let Some((element, depth)) = stack.pop() {
+            result.push_str(&"  ".repeat(depth));
+            match &element {
+                SyntaxElement::Node(node) => {
+                    result.push_str(&format!("{:?}\n", node));
+                    for child in node.children_with_tokens().into_iter().rev() {
+                        stack.push((child, depth + 1));
+                    }
+                }
+                SyntaxElement::Token(token) => result.push_str(&format!("{:?}\n", token)),
+            }
+        }
+        result
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> TokenType {
+        self.green.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn literal(&self) -> Option<&Literal> {
+        self.green.literal.as_ref()
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        TextRange::new(self.offset, self.offset + self.green.text.len())
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// The token after this one in the whole tree, climbing out of the
+    /// nodes that end here.
+    pub fn next_token(&self) -> Option<SyntaxToken> {
+        let mut element = SyntaxElement::Token(self.clone());
+        loop {
+            match element.next_sibling_or_token() {
+                Some(SyntaxElement::Token(token)) => return Some(token),
+                Some(SyntaxElement::Node(node)) => match node.first_token() {
+                    Some(token) => return Some(token),
+                    None => element = SyntaxElement::Node(node),
+                },
+                None => element = SyntaxElement::Node(element.parent()?),
+            }
+        }
+    }
+
+    pub fn prev_token(&self) -> Option<SyntaxToken> {
+        let mut element = SyntaxElement::Token(self.clone());
+        loop {
+            match element.prev_sibling_or_token() {
+                Some(SyntaxElement::Token(token)) => return Some(token),
+                Some(SyntaxElement::Node(node)) => match node.last_token() {
+                    Some(token) => return Some(token),
+                    None => element = SyntaxElement::Node(node),
+                },
+                None => element = SyntaxElement::Node(element.parent()?),
+            }
+        }
+    }
+
+    pub fn next_sibling_or_token(&self) -> Option<SyntaxElement> {
+        SyntaxElement::Token(self.clone()).next_sibling_or_token()
+    }
+
+    pub fn prev_sibling_or_token(&self) -> Option<SyntaxElement> {
+        SyntaxElement::Token(self.clone()).prev_sibling_or_token()
+    }
+}
+
+impl SyntaxElement {
+    pub fn text_range(&self) -> TextRange {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => node.parent(),
+            SyntaxElement::Token(token) => Some(token.parent()),
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            SyntaxElement::Node(node) => node.0.index,
+            SyntaxElement::Token(token) => token.index,
+        }
+    }
+
+    pub fn next_sibling_or_token(&self) -> Option<SyntaxElement> {
+        self.parent()?
+            .child(self.index() + 1, self.text_range().end)
+    }
+
+    pub fn prev_sibling_or_token(&self) -> Option<SyntaxElement> {
+        let parent = self.parent()?;
+        let index = self.index().checked_sub(1)?;
+        let len = parent.0.green.children[index].text_len();
+        parent.child(index, self.text_range().start - len)
+    }
+
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spec::parser::parse;
+
+    use super::*;
+
+    #[test]
+    fn should_keep_the_source_text() {
+        let source = "severo a = 1 // one\n\n  print( a )\n@";
+        let root = parse(source).syntax();
+        assert_eq!(root.text(), source);
+        assert_eq!(root.text_range(), TextRange::new(0, source.len()));
+    }
+
+    #[test]
+    fn should_find_the_token_at_an_offset() {
+        let root = parse("severo value = 10").syntax();
+        assert_eq!(root.token_at_offset(0).unwrap().text(), "severo");
+        assert_eq!(root.token_at_offset(9).unwrap().text(), "value");
+        assert_eq!(root.token_at_offset(12).unwrap().text(), "value");
+        assert_eq!(root.token_at_offset(17).unwrap().text(), "10");
+    }
+
+    #[test]
+    fn should_find_the_covering_element() {
+        let root = parse("print(a + b)").syntax();
+        let element = root.covering_element(TextRange::new(6, 11));
+        assert_eq!(element.into_node().unwrap().kind(), NodeKind::BinaryExpr);
+        let element = root.covering_element(TextRange::empty(7));
+        assert_eq!(element.into_token().unwrap().kind(), TokenType::Whitespace);
+    }
+
+    #[test]
+    fn should_walk_between_tokens() {
+        let root = parse("a = b").syntax();
+        let token = root.token_at_offset(0).unwrap();
+        let next = token.next_token().unwrap();
+        assert_eq!(next.kind(), TokenType::Whitespace);
+        assert_eq!(next.prev_token(), Some(token));
+    }
+
+    #[test]
+    fn should_walk_every_token_and_sibling() {
+        let root = parse("severo f = fun(a) {\n    return a\n}\n\nprint(f(1))\n").syntax();
+        let tokens: Vec<SyntaxToken> = root.descendant_tokens().collect();
+        let walked: Vec<SyntaxToken> =
+            std::iter::successors(root.first_token(), SyntaxToken::next_token).collect();
+        assert_eq!(walked, tokens);
+        let walked_back: Vec<SyntaxToken> =
+            std::iter::successors(root.last_token(), SyntaxToken::prev_token).collect();
+        assert_eq!(walked_back, tokens.into_iter().rev().collect::<Vec<_>>());
+
+        let statements: Vec<SyntaxNode> = root.children().collect();
+        assert_eq!(statements[0].next_sibling(), Some(statements[1].clone()));
+        assert_eq!(statements[1].prev_sibling(), Some(statements[0].clone()));
+        assert_eq!(statements[1].next_sibling(), None);
+    }
+}
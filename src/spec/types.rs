@@ -1,38 +1,136 @@
-#[derive(Debug)]
+use tower_lsp::lsp_types::{Position, Range};
+
+#[derive(PartialEq, Clone, Debug)]
 pub struct Token {
     pub token_type: TokenType,
+    pub span: Span,
+    pub literal: Option<Literal>,
+}
+
+/// A point in the source. `col` is counted in UTF-16 code units, like LSP
+/// positions, while `offset` is the byte offset in the source string.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Location {
+    pub offset: usize,
     pub line: usize,
     pub col: usize,
-    pub literal: Option<Literal>,
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl From<Location> for Position {
+    fn from(location: Location) -> Self {
+        Position {
+            line: location.line as u32,
+            character: location.col as u32,
+        }
+    }
+}
+
+impl From<Span> for Range {
+    fn from(span: Span) -> Self {
+        Range {
+            start: span.start.into(),
+            end: span.end.into(),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum TokenType {
     Var,
     Identifier,
     String,
     Number,
     Print,
+    Fun,
+    Class,
+    If,
+    Elif,
+    Else,
+    While,
+    For,
+    In,
+    Return,
+    This,
+    Super,
+    True,
+    False,
+    Nil,
+    And,
+    Or,
     Equal,
+    EqualEqual,
+    Bang,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Plus,
+    Minus,
+    Star,
+    Slash,
     LeftParen,
     RightParen,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Dot,
+    Semicolon,
+    LineComment,
+    BlockComment,
+    DocComment,
+    Whitespace,
+    Newline,
+    Error,
     Eof,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub enum Literal {
     Identifier(String),
     String(String),
     Number(f64),
+    Comment(String),
 }
 
 #[derive(Debug)]
 pub struct ScanResult {
     pub tokens: Vec<Token>,
+    pub errors: Vec<ScanError>,
 }
 
-#[derive(Debug)]
+#[derive(PartialEq, Debug)]
 pub struct ScanError {
     pub message: String,
-    pub col: usize,
+    pub span: Span,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct EscapeError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TokenType {
+    pub fn is_comment(&self) -> bool {
+        matches!(
+            self,
+            TokenType::LineComment | TokenType::BlockComment | TokenType::DocComment
+        )
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        self.is_comment()
+            || matches!(
+                self,
+                TokenType::Whitespace | TokenType::Newline | TokenType::Error
+            )
+    }
 }
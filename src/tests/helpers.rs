@@ -16,7 +16,7 @@ pub fn create_lsp() -> (DuplexStream, DuplexStream) {
     let (req_client, req_server) = duplex(1024);
     let (res_server, res_client) = duplex(1024);
 
-    let (service, socket) = LspService::new(|client| Backend { client });
+    let (service, socket) = LspService::new(Backend::new);
     tokio::spawn(Server::new(req_server, res_server, socket).serve(service));
     (req_client, res_client)
 }
@@ -94,6 +94,40 @@ pub fn completion_request(id: i64, file_uri: String, line: usize, character: usi
         .finish()
 }
 
+pub fn formatting_request(id: i64, file_uri: String) -> Request {
+    let uri_formatted = format!("file://{}", file_uri);
+    Request::build("textDocument/formatting")
+        .id(id)
+        .params(json!({
+            "textDocument": {"uri": uri_formatted},
+            "options": {"tabSize": 4, "insertSpaces": true}
+        }))
+        .finish()
+}
+
+pub fn execute_command_request(id: i64, command: &str, arguments: Value) -> Request {
+    Request::build("workspace/executeCommand")
+        .id(id)
+        .params(json!({
+            "command": command,
+            "arguments": arguments
+        }))
+        .finish()
+}
+
+pub fn did_open_notification(file_uri: String, text: String) -> Request {
+    Request::build("textDocument/didOpen")
+        .params(json!({
+            "textDocument": {
+                "uri": file_uri,
+                "languageId": "severo",
+                "version": 1,
+                "text": text
+            }
+        }))
+        .finish()
+}
+
 pub fn shutdown_request(id: i64) -> Request {
     Request::build("shutdown").id(id).finish()
 }
@@ -126,6 +160,14 @@ pub fn build_response(id: i64, body: Result<Value, Value>) -> Value {
     }
 }
 
+pub fn build_notification(method: &str, params: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params
+    })
+}
+
 pub fn assert_outputs(left: String, right: String) {
     println!("LEFT:\n{}\nRIGHT:\n{}", left, right);
     let separator = "\r\n\r\n";
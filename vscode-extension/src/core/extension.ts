@@ -1,4 +1,5 @@
 import * as vscode from "vscode";
+import type { Location, Position } from "vscode-languageclient";
 import { LanguageClient, State } from "vscode-languageclient/node.js";
 import {
 	clientId,
@@ -36,6 +37,24 @@ export class SeveroExtension {
 				}
 			});
 
+			this.context?.subscriptions.push(
+				vscode.commands.registerCommand(
+					"severo.showReferences",
+					(uri: string, position: Position, locations: Location[]) => {
+						const converter = this.languageClient?.protocol2CodeConverter;
+						if (!converter) {
+							return;
+						}
+						vscode.commands.executeCommand(
+							"editor.action.showReferences",
+							vscode.Uri.parse(uri),
+							converter.asPosition(position),
+							locations.map(converter.asLocation),
+						);
+					},
+				),
+			);
+
 			this.languageClient.start();
 		} catch (exception) {
 			return Promise.reject("Extension error!");
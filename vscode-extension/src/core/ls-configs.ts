@@ -18,6 +18,7 @@ export const clientOptions = (
 	outputChannel,
 	revealOutputChannelOn: RevealOutputChannelOn.Never,
 	synchronize: {
+		configurationSection: "severo",
 		fileEvents: vscode.workspace.createFileSystemWatcher("**/.clientrc"),
 	},
 });